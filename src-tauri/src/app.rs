@@ -0,0 +1,632 @@
+use sysinfo::{Disks};
+use serde::{Deserialize, Serialize};
+use crate::cache::{CacheManager, PrunePolicy, PruneReport};
+use crate::content::ContentDetection;
+use crate::engine::{DedupeEngine, ProgressPayload, ProgressReporter, ScanConfig, ScanResult};
+use crate::exclusions::{ExclusionMatcher, ExclusionPolicy, ExclusionRule};
+use crate::hashing::HashAlgorithm;
+use crate::io_scheduler::ReadOrder;
+use crate::jobs::{JobRegistry, JobStatus, JobSummary};
+use tauri::{Manager, State};
+use std::sync::Mutex;
+
+struct AppState {
+    cache: Mutex<Option<CacheManager>>,
+    /// Why the cache could not be opened at startup; scans then run without one.
+    cache_error: Option<String>,
+    jobs: Mutex<JobRegistry>,
+    /// Compiled exclusion rules shared by scans and the explorer commands.
+    exclusions: Mutex<ExclusionMatcher>,
+    exclusions_path: std::path::PathBuf,
+}
+
+#[derive(Serialize, Clone)]
+struct JobProgressPayload {
+    job_id: String,
+    #[serde(flatten)]
+    progress: ProgressPayload,
+}
+
+#[derive(Serialize, Clone)]
+struct JobFinishedPayload {
+    job_id: String,
+    status: JobStatus,
+}
+
+/// Records engine progress on the job and forwards it to the webview as `scan-progress` events.
+struct EventReporter {
+    app: tauri::AppHandle,
+    job_id: String,
+}
+
+impl ProgressReporter for EventReporter {
+    fn report(&self, progress: ProgressPayload) {
+        use tauri::Emitter;
+        let state = self.app.state::<AppState>();
+        state.jobs.lock().unwrap().set_progress(&self.job_id, progress.clone());
+        let _ = self.app.emit("scan-progress", JobProgressPayload {
+            job_id: self.job_id.clone(),
+            progress,
+        });
+    }
+
+    fn log(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Scan settings from the frontend, sent as one object. Fields added after the first
+/// release may be omitted and fall back to their defaults.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanOptions {
+    scan_hidden: bool,
+    scan_images: bool,
+    scan_videos: bool,
+    scan_zips: bool,
+    min_file_size: u64,
+    #[serde(default)]
+    content_detection: ContentDetection,
+    #[serde(default)]
+    follow_symlinks: bool,
+    #[serde(default)]
+    hash_algorithm: HashAlgorithm,
+    #[serde(default)]
+    verify_contents: bool,
+    #[serde(default)]
+    progressive_hashing: bool,
+    #[serde(default)]
+    read_order: ReadOrder,
+    /// Size from which BLAKE3 hashes memory-map files; 0 or omitted always streams.
+    #[serde(default)]
+    mmap_threshold: Option<u64>,
+    #[serde(default)]
+    background_io: bool,
+    /// 0 or omitted means unlimited.
+    #[serde(default)]
+    max_read_mbps: Option<u64>,
+    #[serde(default)]
+    find_empty: bool,
+    /// Most cache rows to keep after the scan; 0 or omitted means unlimited.
+    #[serde(default)]
+    cache_limit: Option<u64>,
+}
+
+impl ScanOptions {
+    fn into_config(self, exclusions: ExclusionMatcher) -> ScanConfig {
+        ScanConfig {
+            scan_hidden: self.scan_hidden,
+            scan_images: self.scan_images,
+            scan_videos: self.scan_videos,
+            scan_zips: self.scan_zips,
+            min_file_size: self.min_file_size,
+            content_detection: self.content_detection,
+            exclusions,
+            follow_symlinks: self.follow_symlinks,
+            hash_algorithm: self.hash_algorithm,
+            verify_contents: self.verify_contents,
+            progressive_hashing: self.progressive_hashing,
+            read_order: self.read_order,
+            mmap_threshold: self.mmap_threshold.filter(|&threshold| threshold > 0),
+            background_io: self.background_io,
+            max_read_rate: self.max_read_mbps.filter(|&mbps| mbps > 0).map(|mbps| mbps * 1024 * 1024),
+            find_empty: self.find_empty,
+            auto_prune: self.cache_limit.filter(|&limit| limit > 0).map(|limit| PrunePolicy {
+                max_entries: Some(limit),
+                ..PrunePolicy::default()
+            }),
+        }
+    }
+}
+
+/// Starts a scan on a background thread and returns its job ID immediately.
+/// Poll with `get_scan_status` / `get_scan_result`, or listen for `scan-finished`.
+#[tauri::command]
+fn start_scan(app: tauri::AppHandle, paths: Vec<String>, options: ScanOptions, state: State<AppState>) -> String {
+    let config = options.into_config(state.exclusions.lock().unwrap().clone());
+
+    let (job_id, cancel) = state.jobs.lock().unwrap().create(paths.clone());
+
+    let worker_job_id = job_id.clone();
+    std::thread::spawn(move || {
+        use tauri::Emitter;
+
+        let reporter = EventReporter { app: app.clone(), job_id: worker_job_id.clone() };
+        let state = app.state::<AppState>();
+        // A panic must still finish the job, or the UI would wait on it forever
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            DedupeEngine::new(config, &state.cache)
+                .with_reporter(reporter)
+                .with_cancel_token(cancel)
+                .scan(&paths)
+        }));
+
+        let status = match outcome {
+            Ok(result) => {
+                let status = if result.cancelled { JobStatus::Cancelled } else { JobStatus::Completed };
+                state.jobs.lock().unwrap().finish(&worker_job_id, result);
+                status
+            }
+            Err(payload) => {
+                // Cache writes are transactional, so a panic while it was locked left it consistent
+                state.cache.clear_poison();
+                let message = panic_message(payload.as_ref());
+                eprintln!("Scan {} failed: {}", worker_job_id, message);
+                state.jobs.lock().unwrap_or_else(|e| e.into_inner()).fail(&worker_job_id, message.clone());
+                JobStatus::Failed { message }
+            }
+        };
+        let _ = app.emit("scan-finished", JobFinishedPayload { job_id: worker_job_id, status });
+    });
+
+    job_id
+}
+
+/// The message a panic was raised with, if it was a string.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "the scan stopped unexpectedly".to_string())
+}
+
+/// Asks a running scan to stop. The job then finishes as `cancelled` with a partial result.
+/// Returns false if the job is unknown or already finished.
+#[tauri::command]
+fn cancel_scan(job_id: String, state: State<AppState>) -> bool {
+    state.jobs.lock().unwrap().cancel(&job_id)
+}
+
+#[tauri::command]
+fn get_scan_status(job_id: String, state: State<AppState>) -> Result<JobSummary, String> {
+    state.jobs.lock().unwrap()
+        .status(&job_id)
+        .ok_or_else(|| format!("Unknown scan job: {}", job_id))
+}
+
+/// Returns the result of a finished job, or `None` while it is still running.
+#[tauri::command]
+fn get_scan_result(job_id: String, state: State<AppState>) -> Result<Option<ScanResult>, String> {
+    let jobs = state.jobs.lock().unwrap();
+    if jobs.status(&job_id).is_none() {
+        return Err(format!("Unknown scan job: {}", job_id));
+    }
+    Ok(jobs.result(&job_id).cloned())
+}
+
+/// Lists running and recently finished jobs, newest first.
+#[tauri::command]
+fn list_scan_jobs(state: State<AppState>) -> Vec<JobSummary> {
+    state.jobs.lock().unwrap().list()
+}
+
+#[tauri::command]
+fn get_exclusion_rules(state: State<AppState>) -> Vec<ExclusionRule> {
+    state.exclusions.lock().unwrap().policy().rules.clone()
+}
+
+/// Validates, persists and applies a new rule list. Running scans keep their old rules.
+#[tauri::command]
+fn set_exclusion_rules(rules: Vec<ExclusionRule>, state: State<AppState>) -> Result<(), String> {
+    let policy = ExclusionPolicy { rules };
+    let matcher = policy.compile()?;
+    policy.save(&state.exclusions_path)?;
+    *state.exclusions.lock().unwrap() = matcher;
+    Ok(())
+}
+
+/// Restores the built-in rules and returns them.
+#[tauri::command]
+fn reset_exclusion_rules(state: State<AppState>) -> Result<Vec<ExclusionRule>, String> {
+    let policy = ExclusionPolicy::default();
+    let matcher = policy.compile()?;
+    policy.save(&state.exclusions_path)?;
+    *state.exclusions.lock().unwrap() = matcher;
+    Ok(policy.rules)
+}
+
+#[derive(Serialize)]
+pub struct DriveInfo {
+    name: String,
+    mount_point: String,
+    total_space: u64,
+    available_space: u64,
+    is_removable: bool,
+}
+
+#[derive(Serialize)]
+struct DeletionReport {
+    success_count: usize,
+    fail_count: usize,
+    errors: Vec<String>,
+}
+
+#[tauri::command]
+fn delete_selections(paths: Vec<String>) -> DeletionReport {
+    let mut success_count = 0;
+    let mut fail_count = 0;
+    let mut errors = Vec::new();
+
+    for path in paths {
+        // DETECT EXTERNAL VOLUME:
+        let is_external = path.starts_with("/Volumes/");
+
+        if is_external {
+            // Force Delete Strategy (External/NTFS)
+            let path_obj = std::path::Path::new(&path);
+            let force_result = if path_obj.is_dir() {
+                std::fs::remove_dir_all(&path)
+            } else {
+                std::fs::remove_file(&path)
+            };
+
+            match force_result {
+                Ok(_) => success_count += 1,
+                Err(e) => {
+                    let err_msg = format!("Failed to delete {}: {}", path, e);
+                    eprintln!("{}", err_msg);
+                    errors.push(e.to_string());
+                    fail_count += 1;
+                }
+            }
+        } else {
+            // Standard Trash Strategy (Internal/System)
+            if trash::delete(&path).is_ok() {
+                success_count += 1;
+            } else {
+                // Fallback to force delete
+                let path_obj = std::path::Path::new(&path);
+                let force_result = if path_obj.is_dir() {
+                    std::fs::remove_dir_all(&path)
+                } else {
+                    std::fs::remove_file(&path)
+                };
+
+                match force_result {
+                    Ok(_) => success_count += 1,
+                    Err(e) => {
+                         let err_msg = format!("Failed to delete {}: {}", path, e);
+                         eprintln!("{}", err_msg);
+                         errors.push(e.to_string());
+                         fail_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    DeletionReport {
+        success_count,
+        fail_count,
+        errors,
+    }
+}
+
+/// Deletes items from the empty files and folders report. Unlike `delete_selections`
+/// this never falls back to a recursive delete: a file must still be zero bytes and a
+/// folder must still hold nothing but empty folders.
+#[tauri::command]
+fn delete_empty_items(files: Vec<String>, dirs: Vec<String>) -> DeletionReport {
+    let mut success_count = 0;
+    let mut fail_count = 0;
+    let mut errors = Vec::new();
+
+    let removals = files.iter().map(|path| (path, crate::empty::remove_empty_file(std::path::Path::new(path))))
+        .chain(dirs.iter().map(|path| (path, crate::empty::remove_empty_dir(std::path::Path::new(path)))));
+    for (path, result) in removals {
+        match result {
+            Ok(_) => success_count += 1,
+            Err(e) => {
+                let err_msg = format!("Failed to delete {}: {}", path, e);
+                eprintln!("{}", err_msg);
+                errors.push(err_msg);
+                fail_count += 1;
+            }
+        }
+    }
+
+    DeletionReport {
+        success_count,
+        fail_count,
+        errors,
+    }
+}
+
+use std::process::Command;
+
+#[tauri::command]
+fn get_available_drives_bash() -> Vec<DriveInfo> {
+    // macOS 'df -k' output parsing
+    let output = Command::new("df")
+        .arg("-k")
+        .output()
+        .expect("failed to execute df");
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut drives = Vec::new();
+    
+    // Skip header and parse lines
+    for line in stdout.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() >= 9 {
+            let mount_point = parts[8..].join(" ");
+            let total_k = parts[1].parse::<u64>().unwrap_or(0);
+            let available_k = parts[3].parse::<u64>().unwrap_or(0);
+            
+            let mut name = parts[0].to_string();
+            // macOS Friendly Name Extraction
+            if mount_point.starts_with("/Volumes/") {
+                if let Some(vol_name) = mount_point.strip_prefix("/Volumes/") {
+                    if !vol_name.is_empty() {
+                        name = vol_name.to_string();
+                    }
+                }
+            }
+            
+            drives.push(DriveInfo {
+                name,
+                mount_point,
+                total_space: total_k * 1024,
+                available_space: available_k * 1024,
+                is_removable: parts[0].contains("external") || parts[8].starts_with("/Volumes"),
+            });
+        }
+    }
+    drives
+}
+
+#[tauri::command]
+fn get_system_nodes(app: tauri::AppHandle) -> Vec<DriveInfo> {
+    use tauri::path::BaseDirectory;
+    let mut nodes = Vec::new();
+    let disks = Disks::new_with_refreshed_list();
+
+    let targets = vec![
+        (BaseDirectory::Desktop, "Desktop"),
+        (BaseDirectory::Document, "Documents"),
+        (BaseDirectory::Download, "Downloads"),
+    ];
+
+    for (dir, label) in targets {
+        if let Ok(path) = app.path().resolve("", dir) {
+            let path_str = path.to_string_lossy();
+            
+            // Find the disk that contains this path (longest matching prefix)
+            let matching_disk = disks.iter().filter(|d| {
+                path_str.starts_with(&*d.mount_point().to_string_lossy())
+            }).max_by_key(|d| d.mount_point().to_string_lossy().len());
+
+            nodes.push(DriveInfo {
+                name: label.to_string(),
+                mount_point: path_str.into_owned(),
+                total_space: matching_disk.map(|d| d.total_space()).unwrap_or(0),
+                available_space: matching_disk.map(|d| d.available_space()).unwrap_or(0),
+                is_removable: false,
+            });
+        }
+    }
+    nodes
+}
+
+#[tauri::command]
+fn get_folder_size(path: String) -> u64 {
+    jwalk::WalkDir::new(&path)
+        .skip_hidden(false)
+        .parallelism(jwalk::Parallelism::RayonNewPool(0))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+#[tauri::command]
+fn reveal_in_finder(path: String) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = Command::new("open")
+            .arg("-R")
+            .arg(path)
+            .spawn();
+    }
+}
+
+#[tauri::command]
+fn allow_folder_access(app: tauri::AppHandle, path: String) {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = app.asset_protocol_scope().allow_directory(&path, true);
+    }
+}
+
+#[tauri::command]
+fn reset_cache(state: State<AppState>) -> Result<(), String> {
+    let match_res = state.cache.lock().map_err(|_| "Failed to lock cache mutex".to_string())?;
+    
+    if let Some(cache) = match_res.as_ref() {
+        cache.clear_cache().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Drops stale rows from the cache without discarding the rest, unlike `reset_cache`.
+#[tauri::command]
+fn prune_cache(policy: PrunePolicy, state: State<AppState>) -> Result<PruneReport, String> {
+    let mut cache = state.cache.lock().map_err(|_| "Failed to lock cache mutex".to_string())?;
+    match cache.as_mut() {
+        Some(cache) => cache.prune(&policy).map_err(|e| e.to_string()),
+        None => Err("The hash cache is not available".to_string()),
+    }
+}
+
+/// The error that kept the cache from opening at startup, if any.
+#[tauri::command]
+fn get_cache_error(state: State<AppState>) -> Option<String> {
+    state.cache_error.clone()
+}
+
+#[tauri::command]
+fn get_subdirectories(path: String, state: State<AppState>) -> Vec<DriveInfo> {
+    let mut folders = Vec::new();
+    let exclusions = state.exclusions.lock().unwrap();
+
+    if let Ok(entries) = std::fs::read_dir(&path) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_dir() {
+                    let path_buf = entry.path();
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    
+                    // Basic hidden filter
+                    if name.starts_with('.') { continue; }
+                    
+                    // The scanner's exclusion rules plus the explorer-only ones
+                    if exclusions.is_hidden_in_explorer(&path_buf, true) { continue; }
+
+                    folders.push(DriveInfo {
+                        name,
+                        mount_point: path_buf.to_string_lossy().to_string(),
+                        total_space: 0, // Not applicable for folders
+                        available_space: 0,
+                        is_removable: false,
+                    });
+                }
+            }
+        }
+    }
+    // Sort alphabetically
+    folders.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    folders
+}
+
+#[derive(Serialize)]
+struct FileEntry {
+    name: String,
+    path: String,
+    is_dir: bool,
+    size: u64,
+    created: u64,
+    modified: u64,
+}
+
+#[tauri::command]
+fn read_directory(path: String, state: State<AppState>) -> Vec<FileEntry> {
+    let mut entries_vec = Vec::new();
+    let exclusions = state.exclusions.lock().unwrap();
+
+    if let Ok(entries) = std::fs::read_dir(&path) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            if let Ok(metadata) = entry.metadata() {
+                let path_buf = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+                
+                // Basic hidden filter
+                if name.starts_with('.') { continue; }
+                
+                // The scanner's exclusion rules plus the explorer-only ones
+                if exclusions.is_hidden_in_explorer(&path_buf, metadata.is_dir()) { continue; }
+
+                let created = metadata.created().ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                
+                let modified = metadata.modified().ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+
+                entries_vec.push(FileEntry {
+                    name,
+                    path: path_buf.to_string_lossy().to_string(),
+                    is_dir: metadata.is_dir(),
+                    size: metadata.len(),
+                    created,
+                    modified,
+                });
+            }
+        }
+    }
+    
+    // Sort: Directories first, then files. Both alphabetical.
+    entries_vec.sort_by(|a, b| {
+        if a.is_dir == b.is_dir {
+            a.name.to_lowercase().cmp(&b.name.to_lowercase())
+        } else {
+            b.is_dir.cmp(&a.is_dir) // true (is_dir) comes before false
+        }
+    });
+
+    entries_vec
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            let app_data_dir = app.path().app_data_dir().expect("Failed to get app data dir");
+            std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data dir");
+            let db_path = app_data_dir.join(crate::cache::DB_FILE_NAME);
+
+            // A cache that can't be opened or migrated costs speed, not correctness; run without it
+            let (cache_manager, cache_error) = match CacheManager::new(&db_path) {
+                Ok(cache) => (Some(cache), None),
+                Err(e) => {
+                    eprintln!("Failed to open cache {}: {}", db_path.display(), e);
+                    (None, Some(e.to_string()))
+                }
+            };
+
+            // A broken rules file should not keep the app from starting; fall back to the defaults.
+            let exclusions_path = app_data_dir.join(crate::exclusions::POLICY_FILE_NAME);
+            let exclusions = ExclusionPolicy::load(&exclusions_path)
+                .and_then(|policy| policy.compile())
+                .or_else(|e| {
+                    eprintln!("{}. Using default exclusion rules.", e);
+                    ExclusionPolicy::default().compile()
+                })
+                .expect("Default exclusion rules must compile");
+
+            app.manage(AppState {
+                cache: Mutex::new(cache_manager),
+                cache_error,
+                jobs: Mutex::new(JobRegistry::default()),
+                exclusions: Mutex::new(exclusions),
+                exclusions_path,
+            });
+
+            // Pre-authorize standard system nodes in asset protocol scope for "Installer" feel
+            for dir in [tauri::path::BaseDirectory::Desktop, tauri::path::BaseDirectory::Document, tauri::path::BaseDirectory::Download] {
+                if let Ok(path) = app.path().resolve("", dir) {
+                    let _ = app.asset_protocol_scope().allow_directory(&path, true);
+                }
+            }
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            get_available_drives_bash, 
+            get_system_nodes,
+            start_scan, 
+            cancel_scan,
+            get_scan_status,
+            get_scan_result,
+            list_scan_jobs,
+            get_exclusion_rules,
+            set_exclusion_rules,
+            reset_exclusion_rules,
+            delete_selections,
+            delete_empty_items,
+            reveal_in_finder,
+            allow_folder_access,
+            get_folder_size,
+            reset_cache,
+            prune_cache,
+            get_cache_error,
+            get_subdirectories,
+            read_directory
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
@@ -0,0 +1,365 @@
+//! Headless front-end for the dedupe pipeline.
+//!
+//! Exit codes: 0 = no duplicates, 1 = duplicates found, 2 = usage or runtime error.
+
+use dedupe_algo_lib::cache::{self, CacheManager, PrunePolicy};
+use dedupe_algo_lib::content::ContentDetection;
+use dedupe_algo_lib::engine::{DedupeEngine, ProgressPayload, ProgressReporter, ScanConfig, ScanResult};
+use dedupe_algo_lib::exclusions::{self, ExclusionPolicy, ExclusionRule};
+use dedupe_algo_lib::hashing::HashAlgorithm;
+use dedupe_algo_lib::io_scheduler::ReadOrder;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Mutex;
+
+const USAGE: &str = "\
+Usage: dedupe-algo-cli [OPTIONS] <PATH>...
+
+Scans the given paths and prints groups of duplicate files.
+
+Options:
+      --hidden           Include hidden files and folders
+      --images           Include image files
+      --videos           Include video files
+      --zips             Include archives (zip, tar, gz, 7z, rar)
+      --detect <MODE>    How file types are found: extension, sniff (magic bytes) or all [default: extension]
+      --min-size <SIZE>  Skip files smaller than SIZE bytes (K/M/G suffixes allowed) [default: 50K]
+      --exclusions <FILE>
+                         Read exclusion rules from FILE [default: the desktop app's rules]
+      --exclude <GLOB>   Also skip entries matching GLOB (repeatable)
+  -L, --follow-symlinks  Follow symlinked files and folders (each folder is still read once)
+      --hash <ALGO>      Hash algorithm: blake3, xxh3 or sha256 [default: blake3]
+      --verify           Compare duplicates byte by byte before reporting them
+      --progressive      Hash large candidates in growing chunks, dropping mismatches early
+      --read-order <ORDER>
+                         Order of reads per disk: discovery, inode or physical [default: discovery]
+      --mmap-threshold <SIZE>
+                         Memory-map and hash on all cores files of at least SIZE, e.g. 256M (blake3 only;
+                         a file truncated by another process while mapped crashes the scan) [default: never]
+      --empty            Also list zero-byte files and empty folders
+      --background       Hash at low CPU/disk priority without touching atime or the page cache
+      --max-read-rate <SIZE>
+                         Cap hashing reads at SIZE per second, e.g. 50M
+      --json             Print results as JSON instead of text
+      --cache <FILE>     Use FILE as the hash cache [default: the desktop app's cache]
+      --no-cache         Do not read or write the hash cache
+      --cache-limit <N>  After the scan, evict the least recently seen cache entries above N
+      --prune-missing    After the scan, drop cache entries for deleted files (on mounted volumes)
+      --prune-prefix <PATH>
+                         After the scan, drop cache entries at or below PATH (repeatable)
+      --vacuum           Compact the cache file after pruning
+  -q, --quiet            Do not print progress to stderr
+  -h, --help             Print this help
+
+Documents and audio files are always included, as in the desktop app.
+
+Exit status: 0 if no duplicates were found, 1 if duplicates were found, 2 on error.";
+
+struct CliArgs {
+    paths: Vec<String>,
+    config: ScanConfig,
+    json: bool,
+    cache_path: Option<PathBuf>,
+    no_cache: bool,
+    quiet: bool,
+}
+
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (digits, multiplier) = match value.chars().last()?.to_ascii_uppercase() {
+        'K' => (&value[..value.len() - 1], 1024),
+        'M' => (&value[..value.len() - 1], 1024 * 1024),
+        'G' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn load_exclusions(path: Option<&PathBuf>, extra: &[String]) -> Result<ExclusionPolicy, String> {
+    let mut policy = match path.cloned().or_else(exclusions::default_policy_path) {
+        Some(path) => ExclusionPolicy::load(path)?,
+        None => ExclusionPolicy::default(),
+    };
+    policy.rules.extend(extra.iter().cloned().map(ExclusionRule::Glob));
+    Ok(policy)
+}
+
+fn parse_args() -> Result<Option<CliArgs>, String> {
+    let mut args = CliArgs {
+        paths: Vec::new(),
+        config: ScanConfig {
+            scan_hidden: false,
+            scan_images: false,
+            scan_videos: false,
+            scan_zips: false,
+            min_file_size: 51200, // 50KB, same default as the desktop app
+            content_detection: ContentDetection::Extension,
+            exclusions: ExclusionPolicy::default().compile()?,
+            follow_symlinks: false,
+            hash_algorithm: HashAlgorithm::Blake3,
+            verify_contents: false,
+            progressive_hashing: false,
+            read_order: ReadOrder::Discovery,
+            mmap_threshold: None,
+            background_io: false,
+            find_empty: false,
+            max_read_rate: None,
+            auto_prune: None,
+        },
+        json: false,
+        cache_path: None,
+        no_cache: false,
+        quiet: false,
+    };
+
+    let mut exclusions_path = None;
+    let mut extra_exclusions = Vec::new();
+    let mut prune = PrunePolicy::default();
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--hidden" => args.config.scan_hidden = true,
+            "--images" => args.config.scan_images = true,
+            "--videos" => args.config.scan_videos = true,
+            "--zips" => args.config.scan_zips = true,
+            "-L" | "--follow-symlinks" => args.config.follow_symlinks = true,
+            "--verify" => args.config.verify_contents = true,
+            "--progressive" => args.config.progressive_hashing = true,
+            "--json" => args.json = true,
+            "--no-cache" => args.no_cache = true,
+            "-q" | "--quiet" => args.quiet = true,
+            "--min-size" => {
+                let value = iter.next().ok_or("--min-size requires a value")?;
+                args.config.min_file_size = parse_size(&value)
+                    .ok_or_else(|| format!("invalid size: {}", value))?;
+            }
+            "--detect" => {
+                let value = iter.next().ok_or("--detect requires a value")?;
+                args.config.content_detection = match value.as_str() {
+                    "extension" => ContentDetection::Extension,
+                    "sniff" => ContentDetection::Sniff,
+                    "all" => ContentDetection::All,
+                    _ => return Err(format!("invalid detection mode: {}", value)),
+                };
+            }
+            "--hash" => {
+                let value = iter.next().ok_or("--hash requires a value")?;
+                args.config.hash_algorithm = HashAlgorithm::parse(&value)
+                    .ok_or_else(|| format!("invalid hash algorithm: {}", value))?;
+            }
+            "--background" => args.config.background_io = true,
+            "--empty" => args.config.find_empty = true,
+            "--max-read-rate" => {
+                let value = iter.next().ok_or("--max-read-rate requires a value")?;
+                let rate = parse_size(&value).ok_or_else(|| format!("invalid size: {}", value))?;
+                args.config.max_read_rate = (rate > 0).then_some(rate);
+            }
+            "--mmap-threshold" => {
+                let value = iter.next().ok_or("--mmap-threshold requires a value")?;
+                let threshold = parse_size(&value).ok_or_else(|| format!("invalid size: {}", value))?;
+                args.config.mmap_threshold = (threshold > 0).then_some(threshold);
+            }
+            "--read-order" => {
+                let value = iter.next().ok_or("--read-order requires a value")?;
+                args.config.read_order = match value.as_str() {
+                    "discovery" => ReadOrder::Discovery,
+                    "inode" => ReadOrder::Inode,
+                    "physical" => ReadOrder::Physical,
+                    _ => return Err(format!("invalid read order: {}", value)),
+                };
+            }
+            "--exclusions" => {
+                let value = iter.next().ok_or("--exclusions requires a value")?;
+                exclusions_path = Some(PathBuf::from(value));
+            }
+            "--exclude" => {
+                let value = iter.next().ok_or("--exclude requires a value")?;
+                extra_exclusions.push(value);
+            }
+            "--prune-missing" => prune.remove_missing = true,
+            "--vacuum" => prune.vacuum = true,
+            "--cache-limit" => {
+                let value = iter.next().ok_or("--cache-limit requires a value")?;
+                prune.max_entries = Some(value.parse().map_err(|_| format!("invalid entry count: {}", value))?);
+            }
+            "--prune-prefix" => {
+                let value = iter.next().ok_or("--prune-prefix requires a value")?;
+                prune.prefixes.push(value);
+            }
+            "--cache" => {
+                let value = iter.next().ok_or("--cache requires a value")?;
+                args.cache_path = Some(PathBuf::from(value));
+            }
+            "--" => args.paths.extend(iter.by_ref()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => args.paths.push(arg),
+        }
+    }
+
+    if args.paths.is_empty() {
+        return Err("no paths given".to_string());
+    }
+    args.config.exclusions = load_exclusions(exclusions_path.as_ref(), &extra_exclusions)?.compile()?;
+    if prune.remove_missing || !prune.prefixes.is_empty() || prune.max_entries.is_some() || prune.vacuum {
+        args.config.auto_prune = Some(prune);
+    }
+    Ok(Some(args))
+}
+
+fn open_cache(args: &CliArgs) -> Result<Option<CacheManager>, String> {
+    if args.no_cache {
+        return Ok(None);
+    }
+    let db_path = match &args.cache_path {
+        Some(path) => path.clone(),
+        None => cache::default_db_path().ok_or("could not determine the data directory; use --cache or --no-cache")?,
+    };
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+    }
+    CacheManager::new(&db_path)
+        .map(Some)
+        .map_err(|e| format!("failed to open cache {}: {}", db_path.display(), e))
+}
+
+/// The serialized (snake_case) name of an enum value, as it appears in `--json` output.
+fn snake_case<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value).ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn print_text(result: &ScanResult) {
+    for merged in &result.merged_roots {
+        println!("Skipped {} (already covered by {})", merged.path, merged.merged_into);
+    }
+    for alias in &result.symlink_aliases {
+        println!("Alias {} -> {}", alias.path, alias.target);
+    }
+    if !result.merged_roots.is_empty() || !result.symlink_aliases.is_empty() {
+        println!();
+    }
+
+    let mut wasted: u64 = 0;
+    for (index, group) in result.groups.iter().enumerate() {
+        let size = group.files.first().map(|f| f.size).unwrap_or(0);
+        let hash = group.files.first().and_then(|f| f.full_hash.as_deref()).unwrap_or("-");
+        wasted += group.reclaimable_bytes;
+
+        println!(
+            "Group {} ({} copies, {} bytes each, {} bytes reclaimable, {} {}, verified by {})",
+            index + 1, group.files.len(), size, group.reclaimable_bytes, result.hash_algorithm.as_str(), hash,
+            snake_case(&group.verification)
+        );
+        for file in &group.files {
+            println!("  {}", file.path);
+            for link in &file.hard_links {
+                println!("    = {} (hard link)", link);
+            }
+        }
+        println!();
+    }
+    println!("{} duplicate groups, {} bytes reclaimable", result.groups.len(), wasted);
+
+    if !result.errors.is_empty() {
+        println!();
+        println!("{} paths could not be read:", result.errors.len());
+        for error in &result.errors {
+            let code = error.os_code.map(|c| format!(" (os error {})", c)).unwrap_or_default();
+            println!("  [{} {}{}] {}", snake_case(&error.phase), snake_case(&error.kind), code, error.path);
+        }
+    }
+
+    if !result.empty_files.is_empty() || !result.empty_dirs.is_empty() {
+        println!();
+        println!("{} empty files, {} empty folders:", result.empty_files.len(), result.empty_dirs.len());
+        for path in &result.empty_files {
+            println!("  {}", path);
+        }
+        for path in &result.empty_dirs {
+            println!("  {}/", path);
+        }
+    }
+
+    if !result.unstable_files.is_empty() {
+        println!();
+        println!("{} files changed during the scan and were skipped:", result.unstable_files.len());
+        for file in &result.unstable_files {
+            match (file.current_size, file.current_modified) {
+                (Some(size), Some(modified)) => println!(
+                    "  {} (size {} -> {}, mtime {} -> {})", file.path, file.size, size, file.modified, modified
+                ),
+                _ => println!("  {} (removed)", file.path),
+            }
+        }
+    }
+}
+
+/// Prints progress and status messages to stderr; left out entirely with `--quiet`.
+struct StderrReporter;
+
+impl ProgressReporter for StderrReporter {
+    fn report(&self, progress: ProgressPayload) {
+        eprintln!("[{}/{}] {}", progress.current, progress.total, progress.file);
+    }
+
+    fn log(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let cache = match open_cache(&args) {
+        Ok(cache) => Mutex::new(cache),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    for path in &args.paths {
+        if !std::path::Path::new(path).exists() {
+            eprintln!("error: path does not exist: {}", path);
+            return ExitCode::from(2);
+        }
+    }
+
+    let mut engine = DedupeEngine::new(args.config.clone(), &cache);
+    if !args.quiet {
+        engine = engine.with_reporter(StderrReporter);
+    }
+    let result = engine.scan(&args.paths);
+
+    if args.json {
+        match serde_json::to_string_pretty(&result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("error: failed to serialize results: {}", e);
+                return ExitCode::from(2);
+            }
+        }
+    } else {
+        print_text(&result);
+    }
+
+    if result.groups.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
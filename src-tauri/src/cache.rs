@@ -1,81 +1,549 @@
-use rusqlite::{params, Connection, Result};
-use std::path::Path;
+use crate::hashing::HashAlgorithm;
+use crate::scanner::FileMetadata;
+use rusqlite::{params, Connection, Result, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the cache database inside the app data directory.
+pub const DB_FILE_NAME: &str = "dedupe-algo.db";
+
+/// Location of the desktop app's cache DB, so headless runs share the same hashes.
+pub fn default_db_path() -> Option<PathBuf> {
+    crate::default_app_data_dir().map(|dir| dir.join(DB_FILE_NAME))
+}
+
+/// Hashes cached for a path, with the version of the file they were computed from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CachedEntry {
+    pub size: u64,
+    /// Whole seconds, as shown in the UI.
+    pub modified: u64,
+    /// Nanosecond mtime and ctime; 0 in rows written before they were recorded.
+    pub mtime_ns: i64,
+    pub ctime_ns: i64,
+    pub dev: u64,
+    pub inode: u64,
+    pub partial_hash: Option<String>,
+    pub full_hash: Option<String>,
+}
+
+impl CachedEntry {
+    /// The version of `file` found by the scan, with whatever hashes it has so far.
+    pub fn for_file(file: &FileMetadata) -> Self {
+        Self {
+            size: file.size,
+            modified: file.modified,
+            mtime_ns: file.mtime_ns,
+            ctime_ns: file.ctime_ns,
+            dev: file.dev,
+            inode: file.inode,
+            partial_hash: file.partial_hash.clone(),
+            full_hash: file.full_hash.clone(),
+        }
+    }
+
+    /// Whether the hashes still apply to `file`. Any change of size, inode, or nanosecond
+    /// mtime or ctime invalidates them: in-place rewrites within the same second, and ones
+    /// that restore the old mtime (`rsync -t`, `tar`), still move the ctime.
+    pub fn describes(&self, file: &FileMetadata) -> bool {
+        self.size == file.size
+            && self.inode == file.inode
+            && self.mtime_ns == file.mtime_ns
+            && self.ctime_ns == file.ctime_ns
+    }
+
+    /// Whether hashes cached under another path can be carried over to `file`, found by
+    /// identity after a move. The rename updates the ctime, so the ctime may only have moved
+    /// forward; an older one means another version of the file, e.g. one restored from a
+    /// backup. A file rewritten in place with its old size and mtime restored, and then
+    /// moved, still passes: the fallback trusts that to be rare, while `describes` does not.
+    pub fn may_have_moved_to(&self, file: &FileMetadata) -> bool {
+        self.size == file.size
+            && self.dev == file.dev
+            && self.inode == file.inode
+            && self.mtime_ns == file.mtime_ns
+            && self.ctime_ns <= file.ctime_ns
+    }
+
+    fn from_row(row: &Row) -> Result<(String, Self)> {
+        Ok((row.get(0)?, Self {
+            size: row.get(1)?,
+            modified: row.get(2)?,
+            mtime_ns: row.get(3)?,
+            ctime_ns: row.get(4)?,
+            dev: row.get(5)?,
+            inode: row.get(6)?,
+            partial_hash: row.get(7)?,
+            full_hash: row.get(8)?,
+        }))
+    }
+}
+
+/// Columns read by `CachedEntry::from_row`, in order.
+const ENTRY_COLUMNS: &str = "path, size, modified, mtime_ns, ctime_ns, dev, inode, partial_hash, full_hash";
+
+/// Row written by `batch_upsert`: a path and the entry to store for it.
+pub type CacheUpdate = (String, CachedEntry);
+
+/// `(old path, new path, new ctime_ns)` of a file moved since it was cached.
+pub type Relocation = (String, String, i64);
+
+/// `(dev, inode, size, mtime_ns)`: identifies a file version independently of its path.
+pub type FileIdentity = (u64, u64, u64, i64);
+
+/// Cache file names used by earlier releases, adopted when no current cache exists yet.
+const LEGACY_DB_FILE_NAMES: &[&str] = &["dedupe-pro.db"];
+
+/// One step of the schema history. Steps run in order, each in its own transaction
+/// together with the `schema_version` row that records it.
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// The full schema history. Append new steps; never edit or reorder shipped ones.
+/// Databases from before versioning may already have some of these columns, so the
+/// early steps only add what is missing.
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "create scan_cache", apply: create_scan_cache },
+    Migration { version: 2, description: "tag hashes with their algorithm", apply: add_algorithm },
+    Migration { version: 3, description: "record nanosecond mtime and ctime", apply: add_file_times },
+    Migration { version: 4, description: "record file identities", apply: add_identity },
+    Migration { version: 5, description: "track when entries were last seen", apply: add_last_seen },
+];
+
+fn create_scan_cache(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS scan_cache (
+            path TEXT PRIMARY KEY,
+            size INTEGER NOT NULL,
+            modified INTEGER NOT NULL,
+            partial_hash TEXT,
+            full_hash TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_path_size_mod ON scan_cache (path, size, modified);"
+    )
+}
+
+fn add_algorithm(conn: &Connection) -> Result<()> {
+    // Databases created before hashes were tagged only ever held BLAKE3 hashes
+    add_column_if_missing(conn, "algorithm", "TEXT NOT NULL DEFAULT 'blake3'")
+}
+
+fn add_file_times(conn: &Connection) -> Result<()> {
+    // Rows without these never match a file again and are rehashed once
+    add_column_if_missing(conn, "mtime_ns", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "ctime_ns", "INTEGER NOT NULL DEFAULT 0")
+}
+
+fn add_identity(conn: &Connection) -> Result<()> {
+    // Rows from before identities were recorded have inode 0 and are only found by path
+    add_column_if_missing(conn, "dev", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "inode", "INTEGER NOT NULL DEFAULT 0")?;
+    // Covers the `find_by_identity` lookup; databases that predate versioning may
+    // still carry an index by that name on the whole-second mtime
+    conn.execute_batch(
+        "DROP INDEX IF EXISTS idx_identity;
+        CREATE INDEX idx_identity ON scan_cache (dev, inode, size, mtime_ns);"
+    )
+}
+
+fn add_last_seen(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "last_seen", "INTEGER NOT NULL DEFAULT 0")?;
+    // Existing rows start out as seen now rather than as the first to be evicted
+    conn.execute_batch(
+        "UPDATE scan_cache SET last_seen = strftime('%s', 'now');
+        CREATE INDEX IF NOT EXISTS idx_last_seen ON scan_cache (last_seen);"
+    )
+}
+
+fn add_column_if_missing(conn: &Connection, name: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('scan_cache') WHERE name = ?1",
+        [name],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE scan_cache ADD COLUMN {} {}", name, definition), [])?;
+    }
+    Ok(())
+}
+
+/// Why the cache could not be opened. The database is left as it was before the
+/// failing step, so the app can run without a cache and retry on the next start.
+#[derive(Debug)]
+pub enum CacheError {
+    Sqlite(rusqlite::Error),
+    /// Adopting a legacy cache file or replacing an old backup failed.
+    Io { action: String, source: std::io::Error },
+    /// The copy taken before migrating could not be written; nothing was migrated.
+    Backup { path: PathBuf, source: rusqlite::Error },
+    /// A migration step failed and was rolled back.
+    Migration { version: u32, description: &'static str, backup: Option<PathBuf>, source: rusqlite::Error },
+}
+
+impl std::fmt::Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Sqlite(e) => write!(f, "{}", e),
+            CacheError::Io { action, source } => write!(f, "failed to {}: {}", action, source),
+            CacheError::Backup { path, source } => write!(f, "failed to back up the cache to {}: {}", path.display(), source),
+            CacheError::Migration { version, description, backup, source } => {
+                write!(f, "cache migration {} ({}) failed: {}", version, description, source)?;
+                if let Some(backup) = backup {
+                    write!(f, "; the previous cache was backed up to {}", backup.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<rusqlite::Error> for CacheError {
+    fn from(e: rusqlite::Error) -> Self {
+        CacheError::Sqlite(e)
+    }
+}
+
+/// If `db_path` is the app's cache and doesn't exist yet, renames a cache left next to it
+/// by an earlier release (with its WAL files) so its hashes carry over.
+fn adopt_legacy_db(db_path: &Path) -> std::result::Result<(), CacheError> {
+    if db_path.file_name().is_none_or(|name| name != DB_FILE_NAME) || db_path.exists() {
+        return Ok(());
+    }
+    let Some(legacy) = LEGACY_DB_FILE_NAMES.iter().map(|name| db_path.with_file_name(name)).find(|p| p.exists()) else {
+        return Ok(());
+    };
+    for suffix in ["-wal", "-shm"] {
+        let from = sibling_with_suffix(&legacy, suffix);
+        if from.exists() {
+            let _ = std::fs::rename(&from, sibling_with_suffix(db_path, suffix));
+        }
+    }
+    std::fs::rename(&legacy, db_path).map_err(|source| CacheError::Io {
+        action: format!("rename {} to {}", legacy.display(), db_path.display()),
+        source,
+    })
+}
+
+/// `path` with `suffix` appended to its file name, e.g. `cache.db` -> `cache.db-wal`.
+fn sibling_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Which cache rows to drop. Used by the `prune_cache` command and, through
+/// `ScanConfig::auto_prune`, after every scan.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrunePolicy {
+    /// Drop rows for files that no longer exist. Files on volumes that aren't mounted are kept.
+    pub remove_missing: bool,
+    /// Drop every row at or below these paths.
+    pub prefixes: Vec<String>,
+    /// Keep at most this many rows, evicting the ones least recently seen by a scan.
+    pub max_entries: Option<u64>,
+    /// Compact the database file afterwards.
+    pub vacuum: bool,
+}
+
+/// Rows removed by `CacheManager::prune`, by reason.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PruneReport {
+    pub missing: u64,
+    pub under_prefixes: u64,
+    pub evicted: u64,
+    pub remaining: u64,
+}
+
+impl PruneReport {
+    pub fn removed(&self) -> u64 {
+        self.missing + self.under_prefixes + self.evicted
+    }
+}
+
+/// Whether the file cached at `path` was deleted, as opposed to sitting on a volume that
+/// isn't mounted right now. When a volume is unmounted, the nearest ancestor that still
+/// exists is its mount point on the parent file system, i.e. on another device than `dev`.
+#[cfg(unix)]
+fn file_is_gone(path: &Path, dev: u64) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    // Rows from before devices were recorded can't tell an unplugged drive from a deletion
+    if dev == 0 || !matches!(path.symlink_metadata(), Err(e) if e.kind() == std::io::ErrorKind::NotFound) {
+        return false;
+    }
+    path.ancestors()
+        .skip(1)
+        .find_map(|ancestor| std::fs::metadata(ancestor).ok())
+        .is_some_and(|meta| meta.dev() == dev)
+}
+
+/// Without device numbers, a file counts as gone only if its drive or share is present.
+#[cfg(not(unix))]
+fn file_is_gone(path: &Path, _dev: u64) -> bool {
+    matches!(path.symlink_metadata(), Err(e) if e.kind() == std::io::ErrorKind::NotFound)
+        && path.ancestors().last().is_some_and(|root| root.exists())
+}
 
 pub struct CacheManager {
     conn: Connection,
 }
 
 impl CacheManager {
-    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+    /// Opens the cache at `path`, creating it or bringing its schema up to date.
+    pub fn new<P: AsRef<Path>>(path: P) -> std::result::Result<Self, CacheError> {
+        let path = path.as_ref();
+        adopt_legacy_db(path)?;
         let conn = Connection::open(path)?;
-        let manager = Self { conn };
-        manager.init_table()?;
+        let _ = conn.pragma_update(None, "journal_mode", "WAL");
+        let mut manager = Self { conn };
+        manager.migrate(path)?;
         Ok(manager)
     }
 
-    fn init_table(&self) -> Result<()> {
-        let _ = self.conn.pragma_update(None, "journal_mode", "WAL");
+    /// Highest schema version recorded, or 0 for a new or pre-versioning database.
+    fn schema_version(&self) -> Result<u32> {
         self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS scan_cache (
-                path TEXT PRIMARY KEY,
-                size INTEGER NOT NULL,
-                modified INTEGER NOT NULL,
-                partial_hash TEXT,
-                full_hash TEXT
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at INTEGER NOT NULL
             )",
             [],
         )?;
-        // Index for performance
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_path_size_mod ON scan_cache (path, size, modified)",
+        self.conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))
+    }
+
+    /// Applies the pending steps of `MIGRATIONS` in order. An existing cache is copied
+    /// to `<db>.v<N>.bak` first, so a bad migration never costs the user their hashes.
+    fn migrate(&mut self, path: &Path) -> std::result::Result<(), CacheError> {
+        let current = self.schema_version()?;
+        let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > current).collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let has_data: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'scan_cache'",
             [],
+            |row| row.get(0),
         )?;
+        let backup = if has_data { Some(self.backup(path, current)?) } else { None };
+
+        for migration in pending {
+            let applied = self.conn.transaction().and_then(|tx| {
+                (migration.apply)(&tx)?;
+                tx.execute(
+                    "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, strftime('%s', 'now'))",
+                    params![migration.version, migration.description],
+                )?;
+                tx.commit()
+            });
+            applied.map_err(|source| CacheError::Migration {
+                version: migration.version,
+                description: migration.description,
+                backup: backup.clone(),
+                source,
+            })?;
+        }
         Ok(())
     }
 
-    /// Fetches all cached hashes for a set of paths to minimize DB roundtrips.
-    /// Note: Returns ALL hashes in the DB for easier bulk processing if needed.
-    pub fn get_all_cached_hashes(&self) -> Result<std::collections::HashMap<String, (u64, u64, Option<String>, Option<String>)>> {
-        let mut stmt = self.conn.prepare("SELECT path, size, modified, partial_hash, full_hash FROM scan_cache")?;
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                (
-                    row.get::<_, u64>(1)?,
-                    row.get::<_, u64>(2)?,
-                    row.get::<_, Option<String>>(3)?,
-                    row.get::<_, Option<String>>(4)?
-                )
-            ))
-        })?;
-
-        let mut map = std::collections::HashMap::new();
-        for row in rows {
-            let (path, data) = row?;
-            map.insert(path, data);
+    /// Writes a consistent copy of the database (including any WAL contents) next to it.
+    fn backup(&self, path: &Path, version: u32) -> std::result::Result<PathBuf, CacheError> {
+        let backup = sibling_with_suffix(path, &format!(".v{}.bak", version));
+        if backup.exists() {
+            std::fs::remove_file(&backup).map_err(|source| CacheError::Io {
+                action: format!("replace the old backup {}", backup.display()),
+                source,
+            })?;
+        }
+        self.conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+            .map_err(|source| CacheError::Backup { path: backup.clone(), source })?;
+        Ok(backup)
+    }
+
+    /// Cached hashes produced by `algorithm` for exactly these paths. The paths go into a
+    /// temporary table joined against the primary key, so only matching rows are read.
+    pub fn get_cached_hashes<'p>(&mut self, paths: impl IntoIterator<Item = &'p str>, algorithm: HashAlgorithm) -> Result<HashMap<String, CachedEntry>> {
+        let tx = self.conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS lookup_paths (path TEXT PRIMARY KEY);
+            DELETE FROM temp.lookup_paths;"
+        )?;
+        {
+            let mut insert = tx.prepare("INSERT OR IGNORE INTO temp.lookup_paths (path) VALUES (?1)")?;
+            for path in paths {
+                insert.execute([path])?;
+            }
         }
+        let map = {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM scan_cache JOIN temp.lookup_paths USING (path) WHERE algorithm = ?1",
+                ENTRY_COLUMNS
+            ))?;
+            let rows = stmt.query_map([algorithm.as_str()], CachedEntry::from_row)?;
+            rows.collect::<Result<HashMap<_, _>>>()?
+        };
+        tx.execute("DELETE FROM temp.lookup_paths", [])?;
+        tx.commit()?;
         Ok(map)
     }
 
-    pub fn batch_upsert(&mut self, updates: Vec<(String, u64, u64, Option<String>, Option<String>)>) -> Result<()> {
+    /// Looks up hashes by file identity rather than path, for files that were moved or renamed
+    /// since they were cached. Returns the cached path with each entry found. Like
+    /// `get_cached_hashes`, the identities are joined from a temporary table in one query.
+    ///
+    /// The ctime is not compared here, since a rename updates it on most file systems; see
+    /// `CachedEntry::may_have_moved_to`.
+    pub fn find_by_identity(&mut self, identities: &[FileIdentity], algorithm: HashAlgorithm) -> Result<HashMap<FileIdentity, (String, CachedEntry)>> {
+        let tx = self.conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TEMP TABLE IF NOT EXISTS lookup_identities (
+                dev INTEGER NOT NULL, inode INTEGER NOT NULL, size INTEGER NOT NULL, mtime_ns INTEGER NOT NULL
+            );
+            DELETE FROM temp.lookup_identities;"
+        )?;
+        {
+            let mut insert = tx.prepare("INSERT INTO temp.lookup_identities (dev, inode, size, mtime_ns) VALUES (?1, ?2, ?3, ?4)")?;
+            for &(dev, inode, size, mtime_ns) in identities.iter().filter(|identity| identity.1 != 0) {
+                insert.execute(params![dev, inode, size, mtime_ns])?;
+            }
+        }
+        let mut map = HashMap::new();
+        {
+            let mut stmt = tx.prepare(&format!(
+                "SELECT {} FROM scan_cache
+                 WHERE algorithm = ?1
+                   AND (dev, inode, size, mtime_ns) IN (SELECT dev, inode, size, mtime_ns FROM temp.lookup_identities)",
+                ENTRY_COLUMNS
+            ))?;
+            for row in stmt.query_map([algorithm.as_str()], CachedEntry::from_row)? {
+                let (path, entry) = row?;
+                // Several rows can share an identity (e.g. stale hard-link paths); any one will do
+                map.entry((entry.dev, entry.inode, entry.size, entry.mtime_ns)).or_insert((path, entry));
+            }
+        }
+        tx.execute("DELETE FROM temp.lookup_identities", [])?;
+        tx.commit()?;
+        Ok(map)
+    }
+
+    /// Re-keys rows whose file now lives at a different path, recording the ctime the move
+    /// gave it so the row keeps describing the file. Any row already stored under the new
+    /// path describes a file that is gone, so it is replaced.
+    pub fn relocate(&mut self, moves: &[Relocation]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut delete = tx.prepare("DELETE FROM scan_cache WHERE path = ?1")?;
+            let mut rename = tx.prepare("UPDATE scan_cache SET path = ?2, ctime_ns = ?3 WHERE path = ?1")?;
+            for (from, to, ctime_ns) in moves {
+                delete.execute([to])?;
+                rename.execute(params![from, to, ctime_ns])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Writes hashes produced by `algorithm` and marks the rows as seen now. A hash missing from an update is kept from the
+    /// existing row only if that row describes the same file version and algorithm.
+    pub fn batch_upsert(&mut self, updates: Vec<CacheUpdate>, algorithm: HashAlgorithm) -> Result<()> {
         let tx = self.conn.transaction()?;
         {
             let mut stmt = tx.prepare(
-                "INSERT INTO scan_cache (path, size, modified, partial_hash, full_hash)
-                 VALUES (?1, ?2, ?3, ?4, ?5)
+                "INSERT INTO scan_cache (path, size, modified, mtime_ns, ctime_ns, dev, inode, partial_hash, full_hash, algorithm, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, strftime('%s', 'now'))
                  ON CONFLICT(path) DO UPDATE SET
+                    partial_hash = CASE WHEN scan_cache.algorithm = excluded.algorithm
+                            AND scan_cache.size = excluded.size AND scan_cache.inode = excluded.inode
+                            AND scan_cache.mtime_ns = excluded.mtime_ns AND scan_cache.ctime_ns = excluded.ctime_ns
+                        THEN COALESCE(excluded.partial_hash, scan_cache.partial_hash)
+                        ELSE excluded.partial_hash END,
+                    full_hash = CASE WHEN scan_cache.algorithm = excluded.algorithm
+                            AND scan_cache.size = excluded.size AND scan_cache.inode = excluded.inode
+                            AND scan_cache.mtime_ns = excluded.mtime_ns AND scan_cache.ctime_ns = excluded.ctime_ns
+                        THEN COALESCE(excluded.full_hash, scan_cache.full_hash)
+                        ELSE excluded.full_hash END,
                     size = excluded.size,
                     modified = excluded.modified,
-                    partial_hash = COALESCE(excluded.partial_hash, scan_cache.partial_hash),
-                    full_hash = COALESCE(excluded.full_hash, scan_cache.full_hash)"
+                    mtime_ns = excluded.mtime_ns,
+                    ctime_ns = excluded.ctime_ns,
+                    dev = excluded.dev,
+                    inode = excluded.inode,
+                    algorithm = excluded.algorithm,
+                    last_seen = excluded.last_seen"
             )?;
-            for (path, size, mod_time, ph, fh) in updates {
-                stmt.execute(params![path, size, mod_time, ph, fh])?;
+            for (path, e) in updates {
+                stmt.execute(params![
+                    path, e.size, e.modified, e.mtime_ns, e.ctime_ns, e.dev, e.inode,
+                    e.partial_hash, e.full_hash, algorithm.as_str()
+                ])?;
             }
         }
         tx.commit()
     }
 
+    /// Removes rows as described by `policy`: missing files first, then prefixes, then the
+    /// least recently seen rows above `max_entries`.
+    pub fn prune(&mut self, policy: &PrunePolicy) -> Result<PruneReport> {
+        let mut report = PruneReport::default();
+
+        let gone: Vec<String> = if policy.remove_missing {
+            let mut stmt = self.conn.prepare("SELECT path, dev FROM scan_cache")?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))?;
+            let mut gone = Vec::new();
+            for row in rows {
+                let (path, dev) = row?;
+                if file_is_gone(Path::new(&path), dev) {
+                    gone.push(path);
+                }
+            }
+            gone
+        } else {
+            Vec::new()
+        };
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut delete = tx.prepare("DELETE FROM scan_cache WHERE path = ?1")?;
+            for path in &gone {
+                report.missing += delete.execute([path])? as u64;
+            }
+
+            let mut delete_prefix = tx.prepare(
+                "DELETE FROM scan_cache WHERE path = ?1 OR substr(path, 1, length(?2)) = ?2"
+            )?;
+            for prefix in policy.prefixes.iter().filter(|p| !p.is_empty()) {
+                // A bare "/" trims to "" and then matches every absolute path
+                let prefix = prefix.trim_end_matches(std::path::MAIN_SEPARATOR);
+                let below = format!("{}{}", prefix, std::path::MAIN_SEPARATOR);
+                report.under_prefixes += delete_prefix.execute([prefix, below.as_str()])? as u64;
+            }
+
+            if let Some(max_entries) = policy.max_entries {
+                report.evicted = tx.execute(
+                    "DELETE FROM scan_cache WHERE path IN (
+                        SELECT path FROM scan_cache ORDER BY last_seen, path
+                        LIMIT MAX(0, (SELECT COUNT(*) FROM scan_cache) - ?1)
+                    )",
+                    [max_entries],
+                )? as u64;
+            }
+            report.remaining = tx.query_row("SELECT COUNT(*) FROM scan_cache", [], |row| row.get(0))?;
+        }
+        tx.commit()?;
+
+        if policy.vacuum {
+            self.conn.execute("VACUUM", [])?;
+        }
+        Ok(report)
+    }
+
     pub fn clear_cache(&self) -> Result<()> {
         self.conn.execute("DELETE FROM scan_cache", [])?;
         // Optional: VACUUM to reclaim space, though WAL mode usually handles it well enough.
@@ -83,3 +551,204 @@ impl CacheManager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{metadata, temp_root, write};
+
+    fn entry(size: u64, dev: u64) -> CachedEntry {
+        CachedEntry { size, dev, inode: 1, full_hash: Some("hash".into()), ..Default::default() }
+    }
+
+    #[test]
+    fn describes_rejects_any_change_of_version() {
+        let (_dir, root) = temp_root();
+        let file = metadata(&write(&root, "a.pdf", b"contents"));
+        let cached = CachedEntry::for_file(&file);
+        assert!(cached.describes(&file));
+
+        let changes: [fn(&mut FileMetadata); 4] = [
+            |f| f.size += 1,
+            |f| f.inode += 1,
+            |f| f.mtime_ns += 1,
+            |f| f.ctime_ns += 1,
+        ];
+        for change in changes {
+            let mut changed = file.clone();
+            change(&mut changed);
+            assert!(!cached.describes(&changed));
+        }
+
+        // Whole-second mtime and device alone don't matter
+        let mut moved = file.clone();
+        moved.modified += 1;
+        moved.dev += 1;
+        assert!(cached.describes(&moved));
+    }
+
+    #[test]
+    fn moves_may_only_advance_the_ctime() {
+        let (_dir, root) = temp_root();
+        let file = metadata(&write(&root, "a.pdf", b"contents"));
+        let cached = CachedEntry::for_file(&file);
+        assert!(cached.may_have_moved_to(&file));
+
+        let mut renamed = file.clone();
+        renamed.ctime_ns += 1;
+        assert!(cached.may_have_moved_to(&renamed));
+
+        let changes: [fn(&mut FileMetadata); 5] = [
+            |f| f.size += 1,
+            |f| f.dev += 1,
+            |f| f.inode += 1,
+            |f| f.mtime_ns += 1,
+            |f| f.ctime_ns -= 1,
+        ];
+        for change in changes {
+            let mut changed = file.clone();
+            change(&mut changed);
+            assert!(!cached.may_have_moved_to(&changed));
+        }
+    }
+
+    fn identity_index_columns(cache: &CacheManager) -> Vec<String> {
+        let mut stmt = cache.conn.prepare("SELECT name FROM pragma_index_info('idx_identity') ORDER BY seqno").unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn migrates_baseline_schema() {
+        let (_dir, root) = temp_root();
+        let db = root.join(DB_FILE_NAME);
+        {
+            let conn = Connection::open(&db).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE scan_cache (
+                    path TEXT PRIMARY KEY,
+                    size INTEGER NOT NULL,
+                    modified INTEGER NOT NULL,
+                    partial_hash TEXT,
+                    full_hash TEXT
+                );
+                CREATE INDEX idx_path_size_mod ON scan_cache (path, size, modified);
+                INSERT INTO scan_cache VALUES ('/data/a.pdf', 10, 20, 'partial', 'full');"
+            ).unwrap();
+        }
+
+        let mut cache = CacheManager::new(&db).unwrap();
+        assert!(sibling_with_suffix(&db, ".v0.bak").exists());
+        assert_eq!(cache.schema_version().unwrap(), MIGRATIONS.last().unwrap().version);
+        assert_eq!(identity_index_columns(&cache), ["dev", "inode", "size", "mtime_ns"]);
+
+        let hashes = cache.get_cached_hashes(["/data/a.pdf"], HashAlgorithm::Blake3).unwrap();
+        let old = &hashes["/data/a.pdf"];
+        assert_eq!((old.size, old.modified), (10, 20));
+        assert_eq!(old.partial_hash.as_deref(), Some("partial"));
+        assert_eq!(old.full_hash.as_deref(), Some("full"));
+        // No nanosecond times were recorded, so the row never vouches for a file again
+        assert_eq!((old.mtime_ns, old.ctime_ns, old.inode), (0, 0, 0));
+
+        // Reopening finds nothing left to migrate, so no new backup is taken
+        drop(cache);
+        std::fs::remove_file(sibling_with_suffix(&db, ".v0.bak")).unwrap();
+        CacheManager::new(&db).unwrap();
+        assert!(!sibling_with_suffix(&db, ".v0.bak").exists());
+    }
+
+    #[test]
+    fn migration_replaces_unversioned_identity_index() {
+        let (_dir, root) = temp_root();
+        let db = root.join(DB_FILE_NAME);
+        {
+            // Identities were recorded, and indexed by whole seconds, before the schema was versioned
+            let conn = Connection::open(&db).unwrap();
+            create_scan_cache(&conn).unwrap();
+            add_algorithm(&conn).unwrap();
+            conn.execute_batch(
+                "ALTER TABLE scan_cache ADD COLUMN dev INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE scan_cache ADD COLUMN inode INTEGER NOT NULL DEFAULT 0;
+                CREATE INDEX idx_identity ON scan_cache (dev, inode, size, modified);"
+            ).unwrap();
+        }
+
+        let cache = CacheManager::new(&db).unwrap();
+        assert_eq!(identity_index_columns(&cache), ["dev", "inode", "size", "mtime_ns"]);
+    }
+
+    #[test]
+    fn relocate_records_new_ctime() {
+        let (_dir, root) = temp_root();
+        let mut cache = CacheManager::new(root.join(DB_FILE_NAME)).unwrap();
+        cache.batch_upsert(vec![("/old".into(), entry(7, 1))], HashAlgorithm::Blake3).unwrap();
+        let found = cache.find_by_identity(&[(1, 1, 7, 0)], HashAlgorithm::Blake3).unwrap();
+        assert_eq!(found[&(1, 1, 7, 0)].0, "/old");
+
+        cache.relocate(&[("/old".into(), "/new".into(), 42)]).unwrap();
+        let hashes = cache.get_cached_hashes(["/old", "/new"], HashAlgorithm::Blake3).unwrap();
+        assert!(!hashes.contains_key("/old"));
+        assert_eq!(hashes["/new"].ctime_ns, 42);
+        assert_eq!(hashes["/new"].full_hash.as_deref(), Some("hash"));
+    }
+
+    fn cached_paths(cache: &CacheManager) -> Vec<String> {
+        let mut stmt = cache.conn.prepare("SELECT path FROM scan_cache ORDER BY path").unwrap();
+        stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn prune_evicts_least_recently_seen() {
+        let (_dir, root) = temp_root();
+        let mut cache = CacheManager::new(root.join(DB_FILE_NAME)).unwrap();
+        let updates = ["/a", "/b", "/c", "/d", "/e"].iter()
+            .map(|path| (path.to_string(), entry(1, 1)))
+            .collect();
+        cache.batch_upsert(updates, HashAlgorithm::Blake3).unwrap();
+        for (path, seen) in [("/a", 5), ("/b", 1), ("/c", 4), ("/d", 2), ("/e", 3)] {
+            cache.conn.execute("UPDATE scan_cache SET last_seen = ?2 WHERE path = ?1", params![path, seen]).unwrap();
+        }
+
+        let report = cache.prune(&PrunePolicy { max_entries: Some(3), ..Default::default() }).unwrap();
+        assert_eq!((report.evicted, report.remaining), (2, 3));
+        assert_eq!(cached_paths(&cache), ["/a", "/c", "/e"]);
+
+        let report = cache.prune(&PrunePolicy { max_entries: Some(10), ..Default::default() }).unwrap();
+        assert_eq!(report.removed(), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prune_removes_rows_at_or_below_prefix() {
+        let (_dir, root) = temp_root();
+        let mut cache = CacheManager::new(root.join(DB_FILE_NAME)).unwrap();
+        let updates = ["/data", "/data/a", "/data/sub/b", "/database/c", "/other/d"].iter()
+            .map(|path| (path.to_string(), entry(1, 1)))
+            .collect();
+        cache.batch_upsert(updates, HashAlgorithm::Blake3).unwrap();
+
+        let policy = PrunePolicy { prefixes: vec!["/data/".into(), String::new()], ..Default::default() };
+        let report = cache.prune(&policy).unwrap();
+        assert_eq!((report.under_prefixes, report.remaining), (3, 2));
+        assert_eq!(cached_paths(&cache), ["/database/c", "/other/d"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prune_keeps_missing_files_on_other_devices() {
+        let (_dir, root) = temp_root();
+        let mut cache = CacheManager::new(root.join(DB_FILE_NAME)).unwrap();
+        let dev = metadata(&write(&root, "present.pdf", b"x")).dev;
+        let path = |name: &str| root.join(name).to_string_lossy().into_owned();
+
+        cache.batch_upsert(vec![
+            (path("present.pdf"), entry(1, dev)),
+            (path("deleted.pdf"), entry(1, dev)),
+            (path("unmounted.pdf"), entry(1, dev.wrapping_add(1))),
+            (path("unknown-device.pdf"), entry(1, 0)),
+        ], HashAlgorithm::Blake3).unwrap();
+
+        let report = cache.prune(&PrunePolicy { remove_missing: true, ..Default::default() }).unwrap();
+        assert_eq!((report.missing, report.remaining), (1, 3));
+        assert!(!cached_paths(&cache).contains(&path("deleted.pdf")));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::io_policy::IoPolicy;
+use crate::scanner::read_up_to;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How the scanner decides which files to keep and what they are.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentDetection {
+    /// Trust the file extension (fast, no extra reads).
+    #[default]
+    Extension,
+    /// Read the first bytes of each candidate to find its real type.
+    /// Falls back to the extension for formats without a signature (txt, md, ...).
+    Sniff,
+    /// Keep every file regardless of type; types are still sniffed for reporting.
+    All,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FileCategory {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Other,
+}
+
+/// Category for a lowercase extension, or `None` if the scanner doesn't know it.
+pub fn category_for_extension(ext: &str) -> Option<FileCategory> {
+    match ext {
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "heic" | "tiff" | "bmp" | "arw" | "cr2" | "nef"
+        | "dng" | "orf" | "rw2" | "svg" | "psd" | "ai" | "ico" => Some(FileCategory::Image),
+        "mp4" | "mov" | "avi" | "mkv" | "wmv" | "flv" | "webm" | "m4v" | "ts" | "mts" | "m2ts"
+        | "3gp" | "divx" | "vob" => Some(FileCategory::Video),
+        "zip" | "tar" | "gz" | "7z" | "rar" => Some(FileCategory::Archive),
+        "pdf" | "docx" | "xlsx" | "pptx" | "txt" | "md" => Some(FileCategory::Document),
+        "mp3" | "wav" | "flac" | "m4a" | "ogg" => Some(FileCategory::Audio),
+        _ => None,
+    }
+}
+
+pub fn category_for_path(path: &Path) -> Option<FileCategory> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    category_for_extension(&ext)
+}
+
+/// Bytes read for sniffing; enough for every signature `infer` knows.
+const SNIFF_LEN: usize = 8192;
+
+/// Detects the MIME type and category from the file's leading bytes, read through `io`.
+pub fn sniff(path: &Path, io: &IoPolicy) -> Option<(String, FileCategory)> {
+    let mut file = io.open(path).ok()?;
+    let mut head = [0; SNIFF_LEN];
+    let read = read_up_to(&mut file, &mut head);
+    io.finish(&file);
+    let n = read.ok()?;
+    io.consume(n);
+    let kind = infer::get(&head[..n])?;
+    let category = match kind.matcher_type() {
+        infer::MatcherType::Image => FileCategory::Image,
+        infer::MatcherType::Video => FileCategory::Video,
+        infer::MatcherType::Audio => FileCategory::Audio,
+        infer::MatcherType::Archive => FileCategory::Archive,
+        infer::MatcherType::Doc | infer::MatcherType::Book | infer::MatcherType::Text => FileCategory::Document,
+        _ => FileCategory::Other,
+    };
+    Some((kind.mime_type().to_string(), category))
+}
@@ -0,0 +1,123 @@
+use crate::exclusions::ExclusionMatcher;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What the walk saw of each directory below a root, used to find the empty ones.
+#[derive(Default)]
+pub struct DirectoryContents {
+    dirs: Vec<PathBuf>,
+    /// Directories that directly hold a file, link or unreadable entry.
+    occupied: HashSet<PathBuf>,
+}
+
+impl DirectoryContents {
+    /// A real (non-symlinked) directory below the root.
+    pub fn directory(&mut self, path: PathBuf) {
+        self.dirs.push(path);
+    }
+
+    /// Anything that keeps `dir` from being empty.
+    pub fn occupy(&mut self, dir: &Path) {
+        self.occupied.insert(dir.to_path_buf());
+    }
+
+    /// Directories that contain nothing but (recursively) empty directories. Only the
+    /// outermost one of each empty tree is listed, since deleting it removes the rest.
+    ///
+    /// The walk never sees hidden or excluded entries, so each candidate is re-read from
+    /// disk before it is reported: a folder holding only `.DS_Store` or an excluded
+    /// folder is not empty.
+    pub fn empty_dirs(self, exclusions: &ExclusionMatcher) -> Vec<String> {
+        // A directory with content makes every ancestor non-empty too
+        let mut occupied = HashSet::new();
+        for dir in &self.occupied {
+            for ancestor in dir.ancestors() {
+                if !occupied.insert(ancestor.to_path_buf()) { break; }
+            }
+        }
+
+        let candidates: HashSet<&PathBuf> = self.dirs.iter().filter(|d| !occupied.contains(*d)).collect();
+        let mut children: HashMap<&Path, Vec<&PathBuf>> = HashMap::new();
+        let mut outermost = Vec::new();
+        for dir in &candidates {
+            match dir.parent().filter(|parent| candidates.contains(&parent.to_path_buf())) {
+                Some(parent) => children.entry(parent).or_default().push(dir),
+                None => outermost.push(*dir),
+            }
+        }
+
+        let mut empty = Vec::new();
+        while let Some(dir) = outermost.pop() {
+            if is_empty_tree(dir, exclusions) {
+                empty.push(dir.to_string_lossy().into_owned());
+            } else if let Some(subdirs) = children.get(dir.as_path()) {
+                // Something the walk didn't see is in here; its subfolders may still be empty
+                outermost.extend(subdirs);
+            }
+        }
+        empty.sort();
+        empty
+    }
+}
+
+/// Whether `dir` holds only directories, recursively, with nothing excluded or unreadable.
+fn is_empty_tree(dir: &Path, exclusions: &ExclusionMatcher) -> bool {
+    let Ok(mut entries) = std::fs::read_dir(dir) else { return false };
+    entries.all(|entry| {
+        let Ok(entry) = entry else { return false };
+        let path = entry.path();
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        is_dir && !exclusions.is_excluded(&path, true) && is_empty_tree(&path, exclusions)
+    })
+}
+
+/// Deletes a file reported as zero bytes, unless it has gained content since the scan.
+pub fn remove_empty_file(path: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_file() || metadata.len() != 0 {
+        return Err(io::Error::other("no longer an empty file"));
+    }
+    std::fs::remove_file(path)
+}
+
+/// Deletes a folder reported as empty, along with the empty folders inside it. Only
+/// `remove_dir` is used, which refuses a folder that is not empty, so anything added
+/// since the scan is never deleted; the folders holding it are left in place.
+pub fn remove_empty_dir(path: &Path) -> io::Result<()> {
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_empty_dir(&entry.path())?;
+        }
+    }
+    std::fs::remove_dir(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_root;
+
+    #[test]
+    fn removes_only_what_is_still_empty() {
+        let (_dir, root) = temp_root();
+        std::fs::create_dir_all(root.join("hollow/a/b")).unwrap();
+        std::fs::create_dir_all(root.join("filled/a")).unwrap();
+        std::fs::write(root.join("filled/a/new.txt"), b"added after the scan").unwrap();
+        std::fs::write(root.join("empty.txt"), b"").unwrap();
+        std::fs::write(root.join("grown.txt"), b"x").unwrap();
+
+        remove_empty_dir(&root.join("hollow")).unwrap();
+        assert!(!root.join("hollow").exists());
+
+        assert!(remove_empty_dir(&root.join("filled")).is_err());
+        assert!(root.join("filled/a/new.txt").exists());
+
+        remove_empty_file(&root.join("empty.txt")).unwrap();
+        assert!(!root.join("empty.txt").exists());
+        assert!(remove_empty_file(&root.join("grown.txt")).is_err());
+        assert!(root.join("grown.txt").exists());
+        assert!(remove_empty_file(&root.join("filled")).is_err());
+    }
+}
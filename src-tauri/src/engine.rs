@@ -0,0 +1,1197 @@
+use crate::cache::{CacheManager, CacheUpdate, CachedEntry, FileIdentity, PrunePolicy};
+use crate::cancel::CancellationToken;
+use crate::content::ContentDetection;
+use crate::errors::{ErrorLog, ScanError, ScanPhase};
+use crate::exclusions::ExclusionMatcher;
+use crate::hashing::{ContentHasher, HashAlgorithm};
+use crate::io_policy::{self, IoPolicy};
+use crate::io_scheduler::{self, DeviceScheduler, ReadOrder};
+use crate::roots::{self, MergedRoot};
+use crate::verify::{self, Verification};
+use crate::scanner::{self, DirectoryScan, FileMetadata, SymlinkAlias, UnstableFile, VisitedDirs, scan_directory};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// First progressive round reads this much of each candidate; every later round reads 4x more.
+const PROGRESSIVE_FIRST_CHUNK: u64 = 1024 * 1024;
+const PROGRESSIVE_MAX_CHUNK: u64 = 256 * 1024 * 1024;
+
+/// Options shared by the Tauri `start_scan` command and the headless CLI.
+#[derive(Clone, Debug)]
+pub struct ScanConfig {
+    pub scan_hidden: bool,
+    pub scan_images: bool,
+    pub scan_videos: bool,
+    pub scan_zips: bool,
+    pub min_file_size: u64,
+    /// Extension whitelist, magic-byte sniffing, or every file.
+    pub content_detection: ContentDetection,
+    /// Files and folders to skip during traversal.
+    pub exclusions: ExclusionMatcher,
+    /// Walk into symlinked files and folders. Each folder is still read only once.
+    pub follow_symlinks: bool,
+    /// Algorithm for both hashing passes; cached hashes from another algorithm are ignored.
+    pub hash_algorithm: HashAlgorithm,
+    /// Compare every hash group byte by byte before reporting it, splitting groups that differ.
+    pub verify_contents: bool,
+    /// Hash candidates in growing chunks and regroup after each one, so files that differ
+    /// early are never read to the end.
+    pub progressive_hashing: bool,
+    /// Order of reads on each device; `Physical` minimizes seeking on spinning disks.
+    pub read_order: ReadOrder,
+    /// BLAKE3 full hashes of files at least this large are memory-mapped and spread over
+    /// all cores. `None` always streams. Opt-in: if another process truncates a file while
+    /// it is mapped, the read raises SIGBUS and takes down the whole process.
+    pub mmap_threshold: Option<u64>,
+    /// Hash at low CPU and I/O priority without touching atime or the page cache,
+    /// so the machine stays responsive. Disables the memory-mapped path.
+    pub background_io: bool,
+    /// Cap on the combined read rate of the hashing passes, in bytes per second.
+    pub max_read_rate: Option<u64>,
+    /// Report zero-byte files and folders that are empty apart from empty folders.
+    pub find_empty: bool,
+    /// Pruning applied to the cache after every scan, e.g. to keep it under a row limit.
+    pub auto_prune: Option<PrunePolicy>,
+}
+
+/// Files with identical content. Hard links are folded into one entry (see `FileMetadata::hard_links`),
+/// so every entry here is a distinct copy on disk.
+#[derive(Serialize, Clone, Debug)]
+pub struct DuplicateGroup {
+    pub files: Vec<FileMetadata>,
+    /// Bytes actually freed by keeping one copy and deleting the rest, counting only
+    /// copies whose every hard link was found by the scan. Deleting a copy means deleting
+    /// its `hard_links` along with it.
+    pub reclaimable_bytes: u64,
+    pub verification: Verification,
+}
+
+impl DuplicateGroup {
+    pub fn new(files: Vec<FileMetadata>, verification: Verification) -> Self {
+        let size = files.first().map(|f| f.size).unwrap_or(0);
+        let freeable = files.iter()
+            .filter(|f| f.nlink <= 1 + f.hard_links.len() as u64)
+            .count() as u64;
+
+        // Keep a copy that can't be freed anyway if there is one; otherwise one freeable copy stays.
+        let freed_copies = if freeable == files.len() as u64 { freeable.saturating_sub(1) } else { freeable };
+        Self {
+            files,
+            reclaimable_bytes: size * freed_copies,
+            verification,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ScanResult {
+    pub groups: Vec<DuplicateGroup>,
+    /// Algorithm that produced every `partial_hash` and `full_hash` in `groups`.
+    pub hash_algorithm: HashAlgorithm,
+    /// The scan was stopped early; `groups` only covers files fully hashed before that.
+    pub cancelled: bool,
+    /// Requested roots that were skipped because another root already covers them.
+    pub merged_roots: Vec<MergedRoot>,
+    /// Symlinks that were followed (or skipped because their target was already walked).
+    /// Only filled when `ScanConfig::follow_symlinks` is set.
+    pub symlink_aliases: Vec<SymlinkAlias>,
+    /// Folders and files that could not be read, and in which phase.
+    pub errors: Vec<ScanError>,
+    /// Files that changed while they were being hashed. Left out of `groups` and the cache.
+    pub unstable_files: Vec<UnstableFile>,
+    /// Zero-byte files, when `ScanConfig::find_empty` is set. Never part of `groups`.
+    pub empty_files: Vec<String>,
+    /// Outermost folders that contain nothing but empty folders (after exclusions).
+    pub empty_dirs: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProgressPayload {
+    pub current: usize,
+    pub total: usize,
+    pub file: String,
+}
+
+/// Receives progress updates from the hashing passes. Called from rayon worker threads.
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, progress: ProgressPayload);
+
+    /// Status messages such as phase summaries. Discarded unless the reporter shows them.
+    fn log(&self, _message: &str) {}
+}
+
+impl<F> ProgressReporter for F
+where
+    F: Fn(ProgressPayload) + Send + Sync,
+{
+    fn report(&self, progress: ProgressPayload) {
+        self(progress)
+    }
+}
+
+/// Reporter that discards every update.
+pub struct NoopReporter;
+
+impl ProgressReporter for NoopReporter {
+    fn report(&self, _progress: ProgressPayload) {}
+}
+
+/// The 3-pass dedupe pipeline (size, partial hash, full hash), independent of Tauri.
+///
+/// The cache is only locked briefly before and after hashing, so other callers
+/// (e.g. `reset_cache`) are not blocked for the duration of a scan.
+/// All reads go through a `DeviceScheduler`, so each disk gets a suitable number of readers.
+pub struct DedupeEngine<'a> {
+    config: ScanConfig,
+    cache: &'a Mutex<Option<CacheManager>>,
+    reporter: Box<dyn ProgressReporter + 'a>,
+    cancel: CancellationToken,
+    scheduler: DeviceScheduler,
+    /// First-extent offsets already looked up for `ReadOrder::Physical`, by path.
+    physical_offsets: Mutex<HashMap<String, Option<u64>>>,
+    io: IoPolicy,
+}
+
+impl<'a> DedupeEngine<'a> {
+    pub fn new(config: ScanConfig, cache: &'a Mutex<Option<CacheManager>>) -> Self {
+        Self {
+            io: IoPolicy::new(config.background_io, config.max_read_rate),
+            config,
+            cache,
+            reporter: Box::new(NoopReporter),
+            cancel: CancellationToken::new(),
+            scheduler: DeviceScheduler::new(),
+            physical_offsets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_reporter(mut self, reporter: impl ProgressReporter + 'a) -> Self {
+        self.reporter = Box::new(reporter);
+        self
+    }
+
+    /// Lets another thread abort the scan. Hashes computed before cancellation are still cached.
+    pub fn with_cancel_token(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Scans `paths` and returns every group of two or more identical files.
+    /// Overlapping roots are walked once; see `ScanResult::merged_roots`.
+    pub fn scan(&self, paths: &[String]) -> ScanResult {
+        let (scan_roots, merged_roots) = roots::normalize_roots(paths);
+        for merged in &merged_roots {
+            self.reporter.log(&format!("Root {} is already covered by {}. Skipping.", merged.path, merged.merged_into));
+        }
+        let result = if self.config.background_io {
+            // Every pass runs on low-priority threads; the walk keeps its own jwalk pool
+            match io_policy::background_pool() {
+                Ok(pool) => pool.install(|| self.scan_normalized(&scan_roots)),
+                Err(e) => {
+                    self.reporter.log(&format!("Failed to start background threads ({}). Scanning at normal priority.", e));
+                    self.scan_normalized(&scan_roots)
+                }
+            }
+        } else {
+            self.scan_normalized(&scan_roots)
+        };
+        self.auto_prune_cache();
+        ScanResult { merged_roots, ..result }
+    }
+
+    fn auto_prune_cache(&self) {
+        let Some(policy) = &self.config.auto_prune else { return };
+        let mut cache_lock = self.cache.lock().unwrap();
+        let Some(cache) = cache_lock.as_mut() else { return };
+        match cache.prune(policy) {
+            Ok(report) if report.removed() > 0 => {
+                self.reporter.log(&format!("Pruned {} cache entries; {} remain.", report.removed(), report.remaining));
+            }
+            Ok(_) => {}
+            Err(e) => self.reporter.log(&format!("Failed to prune cache: {}", e)),
+        }
+    }
+
+    fn scan_normalized(&self, paths: &[String]) -> ScanResult {
+        let errors = ErrorLog::default();
+        let unstable = Mutex::new(Vec::new());
+        let found = self.collect_files(paths, &errors);
+        let result = self.find_duplicates(collapse_hard_links(found.files), &errors, &unstable);
+
+        let mut unstable_files = unstable.into_inner().unwrap();
+        unstable_files.sort_by(|a, b| a.path.cmp(&b.path));
+        ScanResult {
+            hash_algorithm: self.config.hash_algorithm,
+            symlink_aliases: found.aliases,
+            errors: errors.into_sorted(),
+            unstable_files,
+            empty_files: found.empty_files,
+            empty_dirs: found.empty_dirs,
+            ..result
+        }
+    }
+
+    fn find_duplicates(&self, all_files: Vec<FileMetadata>, errors: &ErrorLog, unstable: &Mutex<Vec<UnstableFile>>) -> ScanResult {
+        if self.cancel.is_cancelled() { return self.cancelled_result(Vec::new()); }
+
+        // Pass 1: Group by Size
+        let potential_dupes = self.group_by_size(all_files);
+        self.reporter.log(&format!("Phase 1 Complete. Potential duplicates by size: {}", potential_dupes.len()));
+
+        if potential_dupes.is_empty() { return ScanResult::default(); }
+
+        // Optimization: Pre-fetch the candidates' hashes from DB to avoid locking inside parallel pass
+        let cached_hashes = self.load_cached_hashes(&potential_dupes);
+
+        // Pass 2: Partial Hash (Parallel)
+        let hashed_files_p2 = self.partial_hash_pass(potential_dupes, &cached_hashes, errors);
+        let hashed_files_p2 = self.drop_unstable(hashed_files_p2, unstable);
+
+        // Persist partial hashes now so they survive a cancelled or failed full pass
+        self.write_cache(&hashed_files_p2);
+        if self.cancel.is_cancelled() { return self.cancelled_result(Vec::new()); }
+
+        let potential_dupes_p3 = group_candidates(hashed_files_p2, |f| f.partial_hash.clone());
+
+        if potential_dupes_p3.is_empty() { return ScanResult::default(); }
+
+        // Pass 3: Full Hash (Parallel), optionally in rounds that drop diverging files early
+        let hashed_files_p3 = if self.config.progressive_hashing {
+            self.progressive_hash_pass(potential_dupes_p3, &cached_hashes, errors)
+        } else {
+            self.full_hash_pass(potential_dupes_p3, &cached_hashes, errors)
+        };
+        let hashed_files_p3 = self.drop_unstable(hashed_files_p3, unstable);
+
+        // Batch Update Cache at the very end (Efficient transaction)
+        self.write_cache(&hashed_files_p3);
+
+        // Final Grouping by (Size, Full Hash). Files skipped after cancellation have no
+        // full hash and drop out here, so partial results only contain confirmed groups.
+        let mut final_groups: HashMap<(u64, String), Vec<FileMetadata>> = HashMap::new();
+        for f in hashed_files_p3 {
+            if let Some(fh) = f.full_hash.clone() {
+                final_groups.entry((f.size, fh)).or_default().push(f);
+            }
+        }
+
+        let hash_groups: Vec<Vec<FileMetadata>> = final_groups.into_values()
+            .filter(|group| group.len() > 1)
+            .collect();
+
+        // Pass 4 (optional): Byte-for-byte verification
+        let groups = if self.config.verify_contents && !self.cancel.is_cancelled() {
+            self.verification_pass(hash_groups, errors)
+        } else {
+            hash_groups.into_iter()
+                .map(|files| DuplicateGroup::new(files, Verification::FullHash))
+                .collect()
+        };
+
+        if self.cancel.is_cancelled() {
+            return self.cancelled_result(groups);
+        }
+        ScanResult { groups, ..ScanResult::default() }
+    }
+
+    fn cancelled_result(&self, groups: Vec<DuplicateGroup>) -> ScanResult {
+        self.reporter.log(&format!("Scan cancelled. Returning {} confirmed groups.", groups.len()));
+        ScanResult { groups, cancelled: true, ..ScanResult::default() }
+    }
+
+    /// Phase 1: Traversal (Parallel across root paths). Errors go to `errors`; the rest is merged and sorted.
+    fn collect_files(&self, paths: &[String], errors: &ErrorLog) -> DirectoryScan {
+        let config = &self.config;
+        let visited = VisitedDirs::default();
+        self.reporter.log(&format!("Starting scan for paths: {:?}", paths));
+        let scans: Vec<_> = paths.par_iter()
+            .map(|path| {
+                let found = scan_directory(path, config, &self.cancel, &visited, &self.io);
+                self.reporter.log(&format!("Scanned path: {}. Found {} files.", path, found.files.len()));
+                found
+            })
+            .collect();
+
+        let mut merged = DirectoryScan::default();
+        for scan in scans {
+            merged.files.extend(scan.files);
+            merged.aliases.extend(scan.aliases);
+            merged.empty_files.extend(scan.empty_files);
+            merged.empty_dirs.extend(scan.empty_dirs);
+            errors.extend(scan.errors);
+        }
+        merged.aliases.sort_by(|a, b| a.path.cmp(&b.path));
+        merged.empty_files.sort();
+        merged.empty_dirs.sort();
+        self.reporter.log(&format!("Total files found in Phase 1: {}", merged.files.len()));
+        merged
+    }
+
+    /// Discards files whose size is unique, since they cannot have duplicates.
+    fn group_by_size(&self, files: Vec<FileMetadata>) -> Vec<FileMetadata> {
+        let mut size_groups: HashMap<u64, Vec<FileMetadata>> = HashMap::new();
+        for file in files {
+            size_groups.entry(file.size).or_default().push(file);
+        }
+
+        size_groups.into_values()
+            .filter(|group| group.len() > 1)
+            .flatten()
+            .collect()
+    }
+
+    /// Cached hashes by path. Files whose path misses are also looked up by (device, inode,
+    /// size, mtime), so moved or renamed files keep their hashes; rows left under a path that
+    /// no longer exists, and whose ctime is not newer than the file's, are moved to the new one.
+    fn load_cached_hashes(&self, files: &[FileMetadata]) -> HashMap<String, CachedEntry> {
+        let mut cache_lock = self.cache.lock().unwrap();
+        let Some(cache) = cache_lock.as_mut() else { return HashMap::new() };
+        let algorithm = self.config.hash_algorithm;
+        let mut cached = cache.get_cached_hashes(files.iter().map(|f| f.path.as_str()), algorithm).unwrap_or_default();
+
+        let unmatched: Vec<&FileMetadata> = files.iter()
+            .filter(|f| f.inode != 0 && !cached.get(&f.path).is_some_and(|entry| entry.describes(f)))
+            .collect();
+        if unmatched.is_empty() { return cached; }
+
+        let identities: Vec<FileIdentity> = unmatched.iter().map(|f| (f.dev, f.inode, f.size, f.mtime_ns)).collect();
+        let found = cache.find_by_identity(&identities, algorithm).unwrap_or_default();
+
+        let mut moves = Vec::new();
+        for f in unmatched {
+            if let Some((old_path, entry)) = found.get(&(f.dev, f.inode, f.size, f.mtime_ns)) {
+                // Only a path that is gone counts as a move. The same path was already rejected
+                // above, and a path that still exists may be a hard link with an equally stale row.
+                if *old_path == f.path || !entry.may_have_moved_to(f) || Path::new(old_path).exists() { continue; }
+                moves.push((old_path.clone(), f.path.clone(), f.ctime_ns));
+                // The rename itself changed the ctime; everything else matched
+                cached.insert(f.path.clone(), CachedEntry { ctime_ns: f.ctime_ns, ..entry.clone() });
+            }
+        }
+        if !moves.is_empty() {
+            self.reporter.log(&format!("Reusing cached hashes for {} moved or renamed files.", moves.len()));
+            let _ = cache.relocate(&moves);
+        }
+        cached
+    }
+
+    /// Re-stats files after a hashing pass and moves any whose size or mtime changed since
+    /// traversal into `unstable`, so a half-written file is never grouped or cached.
+    fn drop_unstable(&self, files: Vec<FileMetadata>, unstable: &Mutex<Vec<UnstableFile>>) -> Vec<FileMetadata> {
+        files.into_par_iter()
+            .filter_map(|f| match UnstableFile::check(&f) {
+                Some(changed) => {
+                    unstable.lock().unwrap().push(changed);
+                    None
+                }
+                None => Some(f),
+            })
+            .collect()
+    }
+
+    /// Upserts every file that has at least one hash in a single transaction.
+    fn write_cache(&self, files: &[FileMetadata]) {
+        let updates_to_cache: Vec<CacheUpdate> = files.iter()
+            .filter(|f| f.partial_hash.is_some() || f.full_hash.is_some())
+            .map(|f| (f.path.clone(), CachedEntry::for_file(f)))
+            .collect();
+
+        if updates_to_cache.is_empty() { return; }
+
+        let mut cache_lock = self.cache.lock().unwrap();
+        if let Some(cache) = cache_lock.as_mut() {
+            let _ = cache.batch_upsert(updates_to_cache, self.config.hash_algorithm);
+        }
+    }
+
+    /// Sorts work into the configured read order. The scheduler keeps this order within each disk.
+    fn order_reads<T>(&self, items: Vec<T>, file_of: impl Fn(&T) -> &FileMetadata) -> Vec<T> {
+        match self.config.read_order {
+            ReadOrder::Discovery => items,
+            ReadOrder::Inode => {
+                let mut items = items;
+                items.sort_by_key(|item| file_of(item).inode);
+                items
+            }
+            ReadOrder::Physical => {
+                let missing: Vec<String> = {
+                    let known = self.physical_offsets.lock().unwrap();
+                    items.iter()
+                        .map(|item| &file_of(item).path)
+                        .filter(|path| !known.contains_key(*path))
+                        .cloned()
+                        .collect()
+                };
+                let found: Vec<(String, Option<u64>)> = missing.into_par_iter()
+                    .map(|path| {
+                        let offset = io_scheduler::physical_offset(Path::new(&path));
+                        (path, offset)
+                    })
+                    .collect();
+
+                let mut known = self.physical_offsets.lock().unwrap();
+                known.extend(found);
+
+                // Files without extent information go last, in inode order
+                let mut keyed: Vec<((bool, u64, u64), T)> = items.into_iter()
+                    .map(|item| {
+                        let f = file_of(&item);
+                        let offset = known.get(&f.path).copied().flatten();
+                        ((offset.is_none(), offset.unwrap_or(0), f.inode), item)
+                    })
+                    .collect();
+                keyed.sort_by_key(|(key, _)| *key);
+                keyed.into_iter().map(|(_, item)| item).collect()
+            }
+        }
+    }
+
+    fn partial_hash_pass(&self, files: Vec<FileMetadata>, cached_hashes: &HashMap<String, CachedEntry>, errors: &ErrorLog) -> Vec<FileMetadata> {
+        let files = self.order_reads(files, |f| f);
+        let total_files = files.len();
+        let processed_count = AtomicUsize::new(0);
+
+        self.scheduler.run(files, |f| f.dev, |mut f| {
+            if self.cancel.is_cancelled() { return f; }
+
+            let current = processed_count.fetch_add(1, Ordering::Relaxed) + 1;
+            if current.is_multiple_of(5) { // Report every 5 files to reduce overhead
+                self.reporter.report(ProgressPayload {
+                    current,
+                    total: total_files,
+                    file: f.path.clone(),
+                });
+            }
+
+            // Check in-memory cache first
+            if let Some(entry) = cached_hashes.get(&f.path).filter(|entry| entry.describes(&f)) {
+                if let Some(ph) = &entry.partial_hash {
+                    f.partial_hash = Some(ph.clone());
+                    return f;
+                }
+            }
+            // Not in cache, compute it. Empty files get no hash and are never grouped.
+            if f.size == 0 { return f; }
+            match scanner::get_partial_hash(&f.path, self.config.hash_algorithm, &self.io) {
+                Ok(hash) => f.partial_hash = Some(hash),
+                Err(e) => errors.push(ScanError::from_hash(Path::new(&f.path), ScanPhase::PartialHash, &e)),
+            }
+            f
+        })
+    }
+
+    fn full_hash_pass(&self, files: Vec<FileMetadata>, cached_hashes: &HashMap<String, CachedEntry>, errors: &ErrorLog) -> Vec<FileMetadata> {
+        // Progress restarts for the full hash phase; it is reported as a second stage.
+        let files = self.order_reads(files, |f| f);
+        let total_full = files.len();
+        let processed_count_full = AtomicUsize::new(0);
+
+        self.scheduler.run(files, |f| f.dev, |mut f| {
+            if self.cancel.is_cancelled() { return f; }
+
+            // Report every file for full hash as it's slower
+            let current = processed_count_full.fetch_add(1, Ordering::Relaxed) + 1;
+            self.reporter.report(ProgressPayload {
+                current,
+                total: total_full,
+                file: f.path.clone(),
+            });
+
+            // Check in-memory cache first
+            if let Some(entry) = cached_hashes.get(&f.path).filter(|entry| entry.describes(&f)) {
+                if let Some(fh) = &entry.full_hash {
+                    f.full_hash = Some(fh.clone());
+                    return f;
+                }
+            }
+            // Not in cache, compute it
+            match scanner::get_full_hash(&f.path, self.config.hash_algorithm, self.config.mmap_threshold, &self.io) {
+                Ok(hash) => f.full_hash = Some(hash),
+                Err(e) => errors.push(ScanError::from_hash(Path::new(&f.path), ScanPhase::FullHash, &e)),
+            }
+            f
+        })
+    }
+
+    /// Full hash computed in rounds of growing chunks. After each round candidates are regrouped
+    /// by the digest of everything read so far, and any file left alone in its group is dropped
+    /// without reading the rest. Survivors end up with the same `full_hash` as `full_hash_pass`.
+    fn progressive_hash_pass(&self, files: Vec<FileMetadata>, cached_hashes: &HashMap<String, CachedEntry>, errors: &ErrorLog) -> Vec<FileMetadata> {
+        let has_cached_full = |f: &FileMetadata| cached_hashes.get(&f.path)
+            .is_some_and(|entry| entry.full_hash.is_some() && entry.describes(f));
+
+        let mut candidates: HashMap<(u64, String), Vec<FileMetadata>> = HashMap::new();
+        for f in files {
+            if let Some(ph) = f.partial_hash.clone() {
+                candidates.entry((f.size, ph)).or_default().push(f);
+            }
+        }
+
+        // A cached full hash can only be compared with other full hashes, so those groups are hashed whole
+        let (cached_groups, fresh_groups): (Vec<_>, Vec<_>) = candidates.into_values()
+            .partition(|group| group.iter().any(has_cached_full));
+        let mut finished = self.full_hash_pass(cached_groups.into_iter().flatten().collect(), cached_hashes, errors);
+
+        let algorithm = self.config.hash_algorithm;
+        let mut groups: Vec<Vec<ProgressiveFile>> = fresh_groups.into_iter()
+            .map(|group| group.into_iter()
+                .map(|file| ProgressiveFile { file, hasher: algorithm.hasher(), offset: 0 })
+                .collect())
+            .collect();
+        let mut chunk = PROGRESSIVE_FIRST_CHUNK;
+
+        while !groups.is_empty() && !self.cancel.is_cancelled() {
+            let total: usize = groups.iter().map(Vec::len).sum();
+            let processed = AtomicUsize::new(0);
+
+            // Tag each file with its group so the round can be scheduled per device, then regrouped
+            let work: Vec<(usize, ProgressiveFile)> = groups.into_iter()
+                .enumerate()
+                .flat_map(|(index, group)| group.into_iter().map(move |p| (index, p)))
+                .collect();
+            let work = self.order_reads(work, |(_, p)| &p.file);
+
+            let advanced = self.scheduler.run(work, |(_, p)| p.file.dev, |(index, mut p)| {
+                if self.cancel.is_cancelled() { return None; }
+
+                let current = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                self.reporter.report(ProgressPayload {
+                    current,
+                    total,
+                    file: p.file.path.clone(),
+                });
+
+                let len = chunk.min(p.file.size - p.offset);
+                match scanner::hash_range(&p.file.path, p.offset, len, p.hasher.as_mut(), &self.io) {
+                    Ok(()) => {
+                        p.offset += len;
+                        Some((index, p))
+                    }
+                    Err(e) => {
+                        errors.push(ScanError::from_hash(Path::new(&p.file.path), ScanPhase::FullHash, &e));
+                        None
+                    }
+                }
+            });
+
+            let mut regrouped: HashMap<(usize, String), Vec<ProgressiveFile>> = HashMap::new();
+            for (index, p) in advanced.into_iter().flatten() {
+                regrouped.entry((index, p.hasher.snapshot_hex())).or_default().push(p);
+            }
+
+            groups = Vec::new();
+            for members in regrouped.into_values().filter(|members| members.len() > 1) {
+                // Members share a size, so they all reach the end in the same round
+                if members[0].offset < members[0].file.size {
+                    groups.push(members);
+                    continue;
+                }
+                finished.extend(members.into_iter().map(|p| {
+                    let mut f = p.file;
+                    f.full_hash = Some(p.hasher.finalize_hex());
+                    f
+                }));
+            }
+            chunk = (chunk * 4).min(PROGRESSIVE_MAX_CHUNK);
+        }
+        finished
+    }
+
+    /// Splits each hash group into runs of byte-identical files. Groups left unchecked
+    /// because of cancellation keep `Verification::FullHash`.
+    fn verification_pass(&self, groups: Vec<Vec<FileMetadata>>, errors: &ErrorLog) -> Vec<DuplicateGroup> {
+        let groups = self.order_reads(groups, |files| &files[0]);
+        let total = groups.len();
+        let processed = AtomicUsize::new(0);
+
+        let verified: Vec<Vec<DuplicateGroup>> = self.scheduler.run(groups, |files| files[0].dev, |files| {
+            if self.cancel.is_cancelled() {
+                return vec![DuplicateGroup::new(files, Verification::FullHash)];
+            }
+
+            let current = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            self.reporter.report(ProgressPayload {
+                current,
+                total,
+                file: files[0].path.clone(),
+            });
+
+            verify::split_identical(files, &self.io, errors).into_iter()
+                .map(|files| DuplicateGroup::new(files, Verification::ByteForByte))
+                .collect()
+        });
+        verified.into_iter().flatten().collect()
+    }
+}
+
+/// A candidate part-way through `progressive_hash_pass`.
+struct ProgressiveFile {
+    file: FileMetadata,
+    hasher: Box<dyn ContentHasher>,
+    /// Bytes hashed so far, from the start of the file.
+    offset: u64,
+}
+
+/// Folds paths that share a (device, inode) into one entry, so a file is hashed once and
+/// hard links are never reported as duplicates of each other. Entries with the same path
+/// (a symlinked file whose target was also scanned) are merged silently.
+fn collapse_hard_links(files: Vec<FileMetadata>) -> Vec<FileMetadata> {
+    let mut by_inode: HashMap<(u64, u64), FileMetadata> = HashMap::new();
+    let mut seen_paths = std::collections::HashSet::new();
+    let mut result = Vec::new();
+
+    for f in files {
+        // No inode information on this platform
+        if f.inode == 0 {
+            if seen_paths.insert(f.path.clone()) {
+                result.push(f);
+            }
+            continue;
+        }
+        match by_inode.entry((f.dev, f.inode)) {
+            std::collections::hash_map::Entry::Vacant(slot) => {
+                slot.insert(f);
+            }
+            std::collections::hash_map::Entry::Occupied(mut slot) => {
+                let existing = slot.get_mut();
+                // With a single link this is the same file reached twice, not a second name
+                if f.nlink <= 1 { continue; }
+                if existing.path != f.path && !existing.hard_links.contains(&f.path) {
+                    existing.hard_links.push(f.path);
+                }
+            }
+        }
+    }
+
+    // Use the lexicographically first path as the representative so results are stable
+    for mut f in by_inode.into_values() {
+        if !f.hard_links.is_empty() {
+            f.hard_links.push(std::mem::take(&mut f.path));
+            f.hard_links.sort();
+            f.path = f.hard_links.remove(0);
+        }
+        result.push(f);
+    }
+    result
+}
+
+/// Groups files by `(size, key)` and keeps only the members of groups with more than one file.
+/// Files without a key (hashing failed) are dropped.
+fn group_candidates<K>(files: Vec<FileMetadata>, key: K) -> Vec<FileMetadata>
+where
+    K: Fn(&FileMetadata) -> Option<String>,
+{
+    let mut groups: HashMap<(u64, String), Vec<FileMetadata>> = HashMap::new();
+    for f in files {
+        if let Some(k) = key(&f) {
+            groups.entry((f.size, k)).or_default().push(f);
+        }
+    }
+
+    groups.into_values()
+        .filter(|group| group.len() > 1)
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ScanErrorKind;
+    use crate::exclusions::ExclusionPolicy;
+    use crate::test_support::{metadata, path_string, pattern, temp_root, write};
+
+    fn test_config() -> ScanConfig {
+        ScanConfig {
+            scan_hidden: false,
+            scan_images: false,
+            scan_videos: false,
+            scan_zips: false,
+            min_file_size: 1,
+            content_detection: ContentDetection::Extension,
+            exclusions: ExclusionPolicy { rules: Vec::new() }.compile().unwrap(),
+            follow_symlinks: false,
+            hash_algorithm: HashAlgorithm::Blake3,
+            verify_contents: false,
+            progressive_hashing: false,
+            read_order: ReadOrder::Discovery,
+            mmap_threshold: None,
+            background_io: false,
+            max_read_rate: None,
+            find_empty: false,
+            auto_prune: None,
+        }
+    }
+
+    fn scan(config: ScanConfig, cache: &Mutex<Option<CacheManager>>, root: &Path) -> ScanResult {
+        DedupeEngine::new(config, cache).scan(&[root.to_string_lossy().into_owned()])
+    }
+
+    /// Paths of every group, sorted within and across groups.
+    fn group_paths(result: &ScanResult) -> Vec<Vec<String>> {
+        let mut groups: Vec<Vec<String>> = result.groups.iter()
+            .map(|group| {
+                let mut paths: Vec<String> = group.files.iter().map(|f| f.path.clone()).collect();
+                paths.sort();
+                paths
+            })
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    #[test]
+    fn pipeline_groups_only_identical_files() {
+        let (_dir, root) = temp_root();
+        let contents = pattern(40_000, 0);
+        let same: Vec<String> = ["a.pdf", "b.pdf", "c.pdf"].iter().map(|name| write(&root, name, &contents)).collect();
+
+        // Same size and same head and tail, so only the full hash tells it apart
+        let mut middle = contents.clone();
+        middle[20_000] ^= 1;
+        write(&root, "middle.pdf", &middle);
+        // Fails the partial hash already
+        let mut head = contents.clone();
+        head[0] ^= 1;
+        write(&root, "head.pdf", &head);
+        write(&root, "shorter.pdf", &contents[1..]);
+        write(&root, "ignored.bin", &contents);
+
+        let result = scan(test_config(), &Mutex::new(None), &root);
+        assert!(result.errors.is_empty());
+        assert_eq!(group_paths(&result), std::slice::from_ref(&same));
+        let group = &result.groups[0];
+        assert!(group.files[0].full_hash.is_some());
+        assert!(group.files.iter().all(|f| f.full_hash == group.files[0].full_hash));
+    }
+
+    #[test]
+    fn cached_hashes_are_reused() {
+        let (_dir, root) = temp_root();
+        let paths = [write(&root, "a.pdf", b"identical contents"), write(&root, "b.pdf", b"identical contents")];
+        let cache = Mutex::new(Some(CacheManager::new(root.join("cache.db")).unwrap()));
+        let first = scan(test_config(), &cache, &root);
+
+        // Planted hashes that still describe the files must be used instead of reading them
+        let planted: Vec<CacheUpdate> = first.groups[0].files.iter()
+            .map(|f| {
+                let mut f = f.clone();
+                f.partial_hash = Some("planted-partial".into());
+                f.full_hash = Some("planted-full".into());
+                (f.path.clone(), CachedEntry::for_file(&f))
+            })
+            .collect();
+        assert_eq!(planted.len(), paths.len());
+        cache.lock().unwrap().as_mut().unwrap().batch_upsert(planted, HashAlgorithm::Blake3).unwrap();
+        let cached = scan(test_config(), &cache, &root);
+        assert_eq!(cached.groups[0].files[0].full_hash.as_deref(), Some("planted-full"));
+    }
+
+    #[test]
+    fn cancelled_scan_keeps_hashes_computed_so_far() {
+        let (_dir, root) = temp_root();
+        let paths = [
+            write(&root, "a1.pdf", b"first pair"),
+            write(&root, "a2.pdf", b"first pair"),
+            write(&root, "b1.pdf", b"the second pair"),
+            write(&root, "b2.pdf", b"the second pair"),
+        ];
+        let cache = Mutex::new(Some(CacheManager::new(root.join("cache.db")).unwrap()));
+
+        // Too few files for the partial pass to report, so this stops the full pass at its first file;
+        // that file is still hashed
+        let cancel = CancellationToken::new();
+        let stop = cancel.clone();
+        let result = DedupeEngine::new(test_config(), &cache)
+            .with_reporter(move |_: ProgressPayload| stop.cancel())
+            .with_cancel_token(cancel)
+            .scan(&[root.to_string_lossy().into_owned()]);
+        assert!(result.cancelled);
+
+        let cached = cache.lock().unwrap().as_mut().unwrap()
+            .get_cached_hashes(paths.iter().map(String::as_str), HashAlgorithm::Blake3)
+            .unwrap();
+        assert_eq!(cached.len(), paths.len());
+        assert!(cached.values().all(|entry| entry.partial_hash.is_some()));
+        // Files already being hashed when the flag was set still finish
+        assert!(cached.values().any(|entry| entry.full_hash.is_some()));
+    }
+
+    #[test]
+    fn scan_cancelled_up_front_returns_nothing() {
+        let (_dir, root) = temp_root();
+        write(&root, "a.pdf", b"same");
+        write(&root, "b.pdf", b"same");
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = DedupeEngine::new(test_config(), &Mutex::new(None))
+            .with_cancel_token(cancel)
+            .scan(&[root.to_string_lossy().into_owned()]);
+        assert!(result.cancelled);
+        assert!(result.groups.is_empty());
+    }
+
+    #[test]
+    fn collapse_hard_links_folds_names_of_one_file() {
+        let (_dir, root) = temp_root();
+        let file = metadata(&write(&root, "b.pdf", b"contents"));
+
+        // The same single-link file reached twice is not a hard link
+        let twice = collapse_hard_links(vec![file.clone(), file.clone()]);
+        assert_eq!(twice.len(), 1);
+        assert!(twice[0].hard_links.is_empty());
+
+        let linked = |path: &str| FileMetadata { path: path.into(), nlink: 3, ..file.clone() };
+        let collapsed = collapse_hard_links(vec![linked("/c"), linked("/a"), linked("/b"), linked("/a")]);
+        assert_eq!(collapsed.len(), 1);
+        assert_eq!(collapsed[0].path, "/a");
+        assert_eq!(collapsed[0].hard_links, ["/b", "/c"]);
+
+        // Without inode numbers only exact path repeats are dropped
+        let no_inode = |path: &str| FileMetadata { path: path.into(), inode: 0, ..file.clone() };
+        assert_eq!(collapse_hard_links(vec![no_inode("/a"), no_inode("/b"), no_inode("/a")]).len(), 2);
+    }
+
+    #[test]
+    fn reclaimable_bytes_skip_copies_linked_from_outside() {
+        let (_dir, root) = temp_root();
+        let file = metadata(&write(&root, "a.pdf", &[7; 100]));
+        let copy = |path: &str, nlink: u64, hard_links: &[&str]| FileMetadata {
+            path: path.into(),
+            nlink,
+            hard_links: hard_links.iter().map(|p| p.to_string()).collect(),
+            ..file.clone()
+        };
+        let reclaimable = |files| DuplicateGroup::new(files, Verification::FullHash).reclaimable_bytes;
+
+        // All copies freeable: keep one
+        assert_eq!(reclaimable(vec![copy("/a", 1, &[]), copy("/b", 1, &[]), copy("/c", 1, &[])]), 200);
+        // Every link of /a was scanned and is deleted with it
+        assert_eq!(reclaimable(vec![copy("/a", 2, &["/a2"]), copy("/b", 1, &[])]), 100);
+        // /c has a link outside the scan and stays on disk anyway, so both others can go
+        assert_eq!(reclaimable(vec![copy("/a", 1, &[]), copy("/b", 1, &[]), copy("/c", 2, &[])]), 200);
+        // Nothing can be freed when every copy is linked from outside
+        assert_eq!(reclaimable(vec![copy("/a", 2, &[]), copy("/b", 2, &[])]), 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links_are_one_copy() {
+        let (_dir, root) = temp_root();
+        let a = write(&root, "a.pdf", b"linked contents");
+        let a2 = root.join("a2.pdf");
+        std::fs::hard_link(&a, &a2).unwrap();
+        let b = write(&root, "b.pdf", b"linked contents");
+
+        let result = scan(test_config(), &Mutex::new(None), &root);
+        assert_eq!(result.groups.len(), 1);
+        let group = &result.groups[0];
+        let mut files: Vec<(String, Vec<String>)> = group.files.iter().map(|f| (f.path.clone(), f.hard_links.clone())).collect();
+        files.sort();
+        assert_eq!(files, [(a, vec![a2.to_string_lossy().into_owned()]), (b.clone(), Vec::new())]);
+        assert_eq!(group.reclaimable_bytes, 15);
+
+        // A lone file and its hard link are one copy, not duplicates
+        std::fs::remove_file(&b).unwrap();
+        assert!(scan(test_config(), &Mutex::new(None), &root).groups.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlinks_report_aliases_under_the_real_path() {
+        let (_dir, root) = temp_root();
+        let real = root.join("real");
+        std::fs::create_dir(&real).unwrap();
+        let p = write(&real, "p.pdf", b"same contents");
+        let q = write(&real, "q.pdf", b"same contents");
+        let alias = root.join("alias");
+        std::os::unix::fs::symlink(&real, &alias).unwrap();
+        // A link back up must not loop
+        std::os::unix::fs::symlink(&root, real.join("up")).unwrap();
+        // A folder outside the root is walked through its link, under its real path
+        let (_outside_dir, outside) = temp_root();
+        let r = write(&outside, "r.pdf", b"same contents");
+        std::os::unix::fs::symlink(&outside, root.join("ext")).unwrap();
+
+        let result = scan(ScanConfig { follow_symlinks: true, ..test_config() }, &Mutex::new(None), &root);
+        let mut expected = [r, p, q];
+        expected.sort();
+        assert_eq!(group_paths(&result), [expected]);
+        assert!(result.groups[0].files.iter().all(|f| f.hard_links.is_empty()));
+        let mut aliases: Vec<(String, String)> = result.symlink_aliases.iter().map(|a| (a.path.clone(), a.target.clone())).collect();
+        aliases.sort();
+        assert_eq!(aliases, [
+            (path_string(&alias), path_string(&real)),
+            (path_string(&root.join("ext")), path_string(&outside)),
+            (path_string(&real.join("up")), path_string(&root)),
+        ]);
+    }
+
+    #[test]
+    fn missing_root_is_a_traversal_error() {
+        let (_dir, root) = temp_root();
+        let missing = root.join("missing");
+        let result = scan(test_config(), &Mutex::new(None), &missing);
+        assert_eq!(result.errors.len(), 1);
+        let error = &result.errors[0];
+        assert_eq!(error.path, path_string(&missing));
+        assert_eq!((error.phase, error.kind), (ScanPhase::Traversal, ScanErrorKind::NotFound));
+        assert!(error.os_code.is_some());
+    }
+
+    #[test]
+    fn file_gone_before_hashing_is_a_hash_error() {
+        let (_dir, root) = temp_root();
+        let kept = write(&root, "kept.pdf", b"contents");
+        let gone = write(&root, "gone.pdf", b"contents");
+        let files = vec![metadata(&kept), metadata(&gone)];
+        std::fs::remove_file(&gone).unwrap();
+
+        let errors = ErrorLog::default();
+        let hashed = DedupeEngine::new(test_config(), &Mutex::new(None)).partial_hash_pass(files, &HashMap::new(), &errors);
+        assert!(hashed.iter().any(|f| f.path == kept && f.partial_hash.is_some()));
+        let errors = errors.into_sorted();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, gone);
+        assert_eq!((errors[0].phase, errors[0].kind), (ScanPhase::PartialHash, ScanErrorKind::NotFound));
+        assert!(errors[0].os_code.is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_folder_is_skipped_and_reported() {
+        use std::os::unix::fs::PermissionsExt;
+        // Permissions don't stop root
+        if unsafe { libc::geteuid() } == 0 { return; }
+
+        let (_dir, root) = temp_root();
+        let locked = root.join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        write(&locked, "a.pdf", b"same");
+        write(&root, "b.pdf", b"same");
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let result = scan(test_config(), &Mutex::new(None), &root);
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(result.groups.is_empty());
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.errors[0].path, path_string(&locked));
+        assert_eq!(result.errors[0].kind, ScanErrorKind::PermissionDenied);
+        assert_eq!(result.errors[0].os_code, Some(libc::EACCES));
+    }
+
+    #[test]
+    fn cached_hashes_of_another_algorithm_are_ignored() {
+        let (_dir, root) = temp_root();
+        write(&root, "a.pdf", b"identical contents");
+        write(&root, "b.pdf", b"identical contents");
+        let cache = Mutex::new(Some(CacheManager::new(root.join("cache.db")).unwrap()));
+
+        let blake3 = scan(test_config(), &cache, &root);
+        let sha256 = scan(ScanConfig { hash_algorithm: HashAlgorithm::Sha256, ..test_config() }, &cache, &root);
+        assert_eq!(sha256.hash_algorithm, HashAlgorithm::Sha256);
+        let (blake3_hash, sha256_hash) = (&blake3.groups[0].files[0].full_hash, &sha256.groups[0].files[0].full_hash);
+        assert!(sha256_hash.is_some());
+        assert_ne!(blake3_hash, sha256_hash);
+        // Switching back rehashes instead of picking up the SHA-256 rows
+        assert_eq!(&scan(test_config(), &cache, &root).groups[0].files[0].full_hash, blake3_hash);
+    }
+
+    #[test]
+    fn verified_groups_are_marked_byte_for_byte() {
+        let (_dir, root) = temp_root();
+        let same = [write(&root, "a.pdf", b"identical contents"), write(&root, "b.pdf", b"identical contents")];
+        let result = scan(ScanConfig { verify_contents: true, ..test_config() }, &Mutex::new(None), &root);
+        assert_eq!(group_paths(&result), [same]);
+        assert_eq!(result.groups[0].verification, Verification::ByteForByte);
+    }
+
+    #[test]
+    fn progressive_hashes_match_full_hashes() {
+        let (_dir, root) = temp_root();
+        // Long enough for several progressive rounds
+        let len = 5 * PROGRESSIVE_FIRST_CHUNK as usize + 7;
+        let contents = pattern(len, 0);
+        let mut tail = contents.clone();
+        *tail.last_mut().unwrap() ^= 1;
+        let mut early = contents.clone();
+        early[PROGRESSIVE_FIRST_CHUNK as usize + 1] ^= 1;
+        let paths = [
+            write(&root, "a.pdf", &contents),
+            write(&root, "b.pdf", &contents),
+            write(&root, "tail-1.pdf", &tail),
+            write(&root, "tail-2.pdf", &tail),
+            write(&root, "early.pdf", &early),
+        ];
+
+        let cache = Mutex::new(None);
+        let no_cache = HashMap::new();
+        let errors = ErrorLog::default();
+        let engine = DedupeEngine::new(test_config(), &cache);
+        let files = engine.partial_hash_pass(paths.iter().map(|path| metadata(path)).collect(), &no_cache, &errors);
+        let full: HashMap<String, Option<String>> = engine.full_hash_pass(files.clone(), &no_cache, &errors)
+            .into_iter().map(|f| (f.path, f.full_hash)).collect();
+        let progressive: HashMap<String, Option<String>> = engine.progressive_hash_pass(files, &no_cache, &errors)
+            .into_iter().map(|f| (f.path, f.full_hash)).collect();
+        assert!(errors.into_sorted().is_empty());
+
+        // The file that differs in the second round is dropped; every survivor matches
+        assert_eq!(progressive.len(), 4);
+        assert!(!progressive.contains_key(&paths[4]));
+        for (path, hash) in &progressive {
+            assert!(hash.is_some());
+            assert_eq!(hash, &full[path]);
+        }
+
+        let grouped = scan(ScanConfig { progressive_hashing: true, ..test_config() }, &cache, &root);
+        assert_eq!(group_paths(&grouped), [paths[..2].to_vec(), paths[2..4].to_vec()]);
+    }
+
+    #[test]
+    fn reads_are_ordered_by_inode_or_extent() {
+        let (_dir, root) = temp_root();
+        let template = metadata(&write(&root, "a.pdf", b"x"));
+        let file = |path: &str, inode: u64| FileMetadata { path: path.into(), inode, ..template.clone() };
+        let files = vec![file("/d", 4), file("/b", 2), file("/c", 3), file("/a", 1)];
+        let order = |read_order, known: &[(&str, Option<u64>)]| -> Vec<String> {
+            let cache = Mutex::new(None);
+            let engine = DedupeEngine::new(ScanConfig { read_order, ..test_config() }, &cache);
+            engine.physical_offsets.lock().unwrap()
+                .extend(known.iter().map(|(path, offset)| (path.to_string(), *offset)));
+            engine.order_reads(files.clone(), |f| f).into_iter().map(|f| f.path).collect()
+        };
+
+        assert_eq!(order(ReadOrder::Discovery, &[]), ["/d", "/b", "/c", "/a"]);
+        assert_eq!(order(ReadOrder::Inode, &[]), ["/a", "/b", "/c", "/d"]);
+        // Files without extent information go last, in inode order
+        let offsets = [("/a", None), ("/b", Some(900)), ("/c", None), ("/d", Some(100))];
+        assert_eq!(order(ReadOrder::Physical, &offsets), ["/d", "/b", "/a", "/c"]);
+    }
+
+    #[test]
+    fn mapped_hashes_match_streamed_hashes() {
+        let (_dir, root) = temp_root();
+        let paths = [
+            write(&root, "small.pdf", b"tiny"),
+            write(&root, "large.pdf", &pattern(3 * 1024 * 1024 + 5, 1)),
+        ];
+        let files: Vec<FileMetadata> = paths.iter().map(|path| metadata(path)).collect();
+
+        let cache = Mutex::new(None);
+        let no_cache = HashMap::new();
+        let errors = ErrorLog::default();
+        let hashes = |mmap_threshold| {
+            let engine = DedupeEngine::new(ScanConfig { mmap_threshold, ..test_config() }, &cache);
+            engine.full_hash_pass(files.clone(), &no_cache, &errors)
+                .into_iter().map(|f| (f.path, f.full_hash)).collect::<HashMap<_, _>>()
+        };
+        let streamed = hashes(None);
+        assert_eq!(hashes(Some(1)), streamed);
+        assert!(errors.into_sorted().is_empty());
+        assert!(streamed.values().all(Option::is_some));
+    }
+
+    #[test]
+    fn file_changed_after_traversal_is_unstable() {
+        let (_dir, root) = temp_root();
+        let paths = [
+            write(&root, "a.pdf", b"identical contents"),
+            write(&root, "b.pdf", b"identical contents"),
+            write(&root, "c.pdf", b"identical contents"),
+        ];
+        let files: Vec<FileMetadata> = paths.iter().map(|path| metadata(path)).collect();
+
+        // Rewritten with the same contents, so only its new mtime gives it away
+        let changed = std::fs::File::options().write(true).open(&paths[2]).unwrap();
+        changed.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+        drop(changed);
+
+        let cache = Mutex::new(Some(CacheManager::new(root.join("cache.db")).unwrap()));
+        let errors = ErrorLog::default();
+        let unstable = Mutex::new(Vec::new());
+        let result = DedupeEngine::new(test_config(), &cache).find_duplicates(files, &errors, &unstable);
+        assert!(errors.into_sorted().is_empty());
+
+        assert_eq!(group_paths(&result), [paths[..2].to_vec()]);
+        let unstable: Vec<String> = unstable.into_inner().unwrap().into_iter().map(|f| f.path).collect();
+        assert_eq!(unstable, [paths[2].clone()]);
+        let cached = cache.lock().unwrap().as_mut().unwrap()
+            .get_cached_hashes(paths.iter().map(String::as_str), HashAlgorithm::Blake3).unwrap();
+        assert!(cached.contains_key(&paths[0]) && cached.contains_key(&paths[1]));
+        assert!(!cached.contains_key(&paths[2]));
+    }
+
+    #[test]
+    fn empty_files_and_outermost_empty_folders_are_listed() {
+        let (_dir, root) = temp_root();
+        let empty = write(&root, "empty.pdf", b"");
+        write(&root, "full.pdf", b"contents");
+        std::fs::create_dir_all(root.join("hollow/a/b")).unwrap();
+        std::fs::create_dir_all(root.join("partly/empty")).unwrap();
+        write(&root.join("partly"), "kept.pdf", b"other contents");
+        // Hidden entries are never walked but still keep their folder
+        std::fs::create_dir(root.join("hidden")).unwrap();
+        write(&root.join("hidden"), ".DS_Store", b"");
+
+        let cache = Mutex::new(None);
+        let result = scan(ScanConfig { find_empty: true, ..test_config() }, &cache, &root);
+        assert!(result.groups.is_empty());
+        assert_eq!(result.empty_files, [empty]);
+        assert_eq!(result.empty_dirs, [path_string(&root.join("hollow")), path_string(&root.join("partly/empty"))]);
+
+        let result = scan(test_config(), &cache, &root);
+        assert!(result.empty_files.is_empty() && result.empty_dirs.is_empty());
+    }
+
+    #[test]
+    fn moved_files_keep_hashes_unless_their_ctime_went_back() {
+        let (_dir, root) = temp_root();
+        let paths = [write(&root, "a.pdf", b"identical contents"), write(&root, "b.pdf", b"identical contents")];
+        let cache = Mutex::new(Some(CacheManager::new(root.join("cache.db")).unwrap()));
+        scan(test_config(), &cache, &root);
+        let plant = |path: &str, ctime_offset: i64| {
+            let mut f = metadata(path);
+            f.full_hash = Some("planted-full".into());
+            let planted = CachedEntry { ctime_ns: f.ctime_ns + ctime_offset, ..CachedEntry::for_file(&f) };
+            cache.lock().unwrap().as_mut().unwrap().batch_upsert(vec![(f.path, planted)], HashAlgorithm::Blake3).unwrap();
+        };
+        let cached_full_hash = |path: &str| {
+            let mut hashes = cache.lock().unwrap().as_mut().unwrap().get_cached_hashes([path], HashAlgorithm::Blake3).unwrap();
+            hashes.remove(path).and_then(|entry| entry.full_hash)
+        };
+
+        // A rename only moves the ctime forward, so the planted hash follows the file
+        plant(&paths[0], 0);
+        plant(&paths[1], 0);
+        let renamed = path_string(&root.join("c.pdf"));
+        std::fs::rename(&paths[1], &renamed).unwrap();
+        let result = scan(test_config(), &cache, &root);
+        assert_eq!(group_paths(&result), [[paths[0].clone(), renamed.clone()]]);
+        assert_eq!(cached_full_hash(&renamed).as_deref(), Some("planted-full"));
+
+        // A cached ctime newer than the file's, even after the rename, belongs to another version
+        plant(&renamed, 3_600_000_000_000);
+        let again = path_string(&root.join("d.pdf"));
+        std::fs::rename(&renamed, &again).unwrap();
+        scan(test_config(), &cache, &root);
+        assert!(cached_full_hash(&again).is_some_and(|hash| hash != "planted-full"));
+    }
+
+    #[test]
+    fn rewrite_that_restores_the_mtime_is_rehashed() {
+        let (_dir, root) = temp_root();
+        let paths = [write(&root, "a.pdf", b"identical contents"), write(&root, "b.pdf", b"identical contents")];
+        let cache = Mutex::new(Some(CacheManager::new(root.join("cache.db")).unwrap()));
+        assert_eq!(scan(test_config(), &cache, &root).groups.len(), 1);
+
+        // Same size and mtime as before, as `rsync -t` leaves it; only the ctime moves
+        let mtime = std::fs::metadata(&paths[1]).unwrap().modified().unwrap();
+        std::fs::write(&paths[1], b"different contents").unwrap();
+        std::fs::File::options().write(true).open(&paths[1]).unwrap().set_modified(mtime).unwrap();
+        assert!(scan(test_config(), &cache, &root).groups.is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Which step of the scan failed on a path.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanPhase {
+    /// Listing a directory (the folder and everything below it was skipped).
+    Traversal,
+    /// Reading a file's size, timestamps or identity.
+    Metadata,
+    PartialHash,
+    FullHash,
+    /// Byte-for-byte comparison of a hash group.
+    Verification,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    NotFound,
+    /// Any other I/O failure; see `os_code` and `message`.
+    Io,
+}
+
+/// A path the scan could not process. Collected instead of logged, so the UI and CLI
+/// can show which folders were skipped and why.
+#[derive(Serialize, Clone, Debug)]
+pub struct ScanError {
+    pub path: String,
+    pub phase: ScanPhase,
+    pub kind: ScanErrorKind,
+    /// Raw `errno` (or Windows error code), when the OS reported one.
+    pub os_code: Option<i32>,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn from_io(path: &Path, phase: ScanPhase, err: &std::io::Error) -> Self {
+        let kind = match err.kind() {
+            std::io::ErrorKind::PermissionDenied => ScanErrorKind::PermissionDenied,
+            std::io::ErrorKind::NotFound => ScanErrorKind::NotFound,
+            _ => ScanErrorKind::Io,
+        };
+        Self {
+            path: path.to_string_lossy().into_owned(),
+            phase,
+            kind,
+            os_code: err.raw_os_error(),
+            message: err.to_string(),
+        }
+    }
+
+    pub fn from_hash(path: &Path, phase: ScanPhase, err: &HashError) -> Self {
+        match err {
+            HashError::Io(e) => Self::from_io(path, phase, e),
+            HashError::Truncated { .. } => Self {
+                path: path.to_string_lossy().into_owned(),
+                phase,
+                kind: ScanErrorKind::Io,
+                os_code: None,
+                message: err.to_string(),
+            },
+        }
+    }
+
+    /// A failure without an underlying `io::Error`.
+    pub fn other(path: &str, phase: ScanPhase, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            phase,
+            kind: ScanErrorKind::Io,
+            os_code: None,
+            message: message.into(),
+        }
+    }
+}
+
+/// Why a content hash could not be computed. A hash is only ever returned for the
+/// complete range that was asked for, never for whatever was read before a failure.
+#[derive(Debug)]
+pub enum HashError {
+    /// Opening, seeking or reading the file failed.
+    Io(std::io::Error),
+    /// The file ended before the expected number of bytes was read, e.g. it shrank mid-scan.
+    Truncated { expected: u64, read: u64 },
+}
+
+impl std::fmt::Display for HashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashError::Io(e) => e.fmt(f),
+            HashError::Truncated { expected, read } => {
+                write!(f, "file shrank while hashing (read {} of {} bytes)", read, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl From<std::io::Error> for HashError {
+    fn from(err: std::io::Error) -> Self {
+        HashError::Io(err)
+    }
+}
+
+/// Thread-safe collector shared by the traversal and hashing passes.
+#[derive(Default)]
+pub struct ErrorLog {
+    errors: Mutex<Vec<ScanError>>,
+}
+
+impl ErrorLog {
+    pub fn push(&self, error: ScanError) {
+        self.errors.lock().unwrap().push(error);
+    }
+
+    pub fn extend(&self, errors: Vec<ScanError>) {
+        self.errors.lock().unwrap().extend(errors);
+    }
+
+    /// All errors, ordered by path and phase for stable output.
+    pub fn into_sorted(self) -> Vec<ScanError> {
+        let mut errors = self.errors.into_inner().unwrap();
+        errors.sort_by(|a, b| a.path.cmp(&b.path).then(a.phase.cmp(&b.phase)));
+        errors
+    }
+}
@@ -0,0 +1,284 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// File name of the persisted policy inside the app data directory.
+pub const POLICY_FILE_NAME: &str = "exclusions.json";
+
+/// Where the desktop app keeps its exclusion rules, so the CLI applies the same ones.
+pub fn default_policy_path() -> Option<PathBuf> {
+    crate::default_app_data_dir().map(|dir| dir.join(POLICY_FILE_NAME))
+}
+
+/// A single rule that removes matching files and folders from scans and the explorer.
+/// Excluded folders are not descended into.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ExclusionRule {
+    /// File or folder name, compared case-insensitively (e.g. `node_modules`).
+    Name(String),
+    /// Absolute path; the path itself and everything below it are excluded.
+    Path(String),
+    /// Glob pattern. Patterns containing `/` match the full path, others match the entry name.
+    Glob(String),
+    /// Regular expression matched against the full path.
+    Regex(String),
+    /// Folder name, compared exactly (e.g. `build`). Files with the same name are kept.
+    Folder(String),
+    /// A rule that only hides entries in the explorer; scans still include them.
+    ExplorerOnly(Box<ExclusionRule>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExclusionPolicy {
+    pub rules: Vec<ExclusionRule>,
+}
+
+impl Default for ExclusionPolicy {
+    /// The lists that used to be hard-coded in the scanner and explorer commands.
+    fn default() -> Self {
+        let mut rules = Vec::new();
+
+        // System locations
+        for path in system_paths() {
+            rules.push(ExclusionRule::Path(path));
+        }
+
+        // Trash
+        for name in [".Trash", ".Trashes", "$RECYCLE.BIN"] {
+            rules.push(ExclusionRule::Name(name.to_string()));
+        }
+
+        // Developer / high-entropy folders; `Build/` or a file named `env` is still scanned
+        for name in [
+            "node_modules", "venv", ".venv", "env", "target", "dist", "build",
+            "__pycache__", ".git", ".hg", ".svn", ".vscode", ".idea",
+        ] {
+            rules.push(ExclusionRule::Folder(name.to_string()));
+        }
+
+        // OS metadata the explorer hides, including chkdsk's "found.000", "found.001", ...
+        for name in [
+            "System Volume Information", "Recovery", "Config.Msi", "$WinREAgent",
+            ".fseventsd", ".Spotlight-V100", ".DocumentRevisions-V100", ".TemporaryItems",
+        ] {
+            rules.push(ExclusionRule::ExplorerOnly(Box::new(ExclusionRule::Name(name.to_string()))));
+        }
+        rules.push(ExclusionRule::ExplorerOnly(Box::new(ExclusionRule::Regex(r"(^|[/\\])found\.\d+$".to_string()))));
+
+        Self { rules }
+    }
+}
+
+/// Absolute paths of the OS's own files. Path rules must be absolute, which on Windows
+/// means they need a drive, so each platform gets its own list.
+#[cfg(not(windows))]
+fn system_paths() -> Vec<String> {
+    [
+        "/System", "/Library", "/bin", "/usr/bin", "/usr/sbin",
+        "/dev", "/proc", "/sys", "/etc", "/var/lib", "/var/cache",
+    ].map(String::from).to_vec()
+}
+
+#[cfg(windows)]
+fn system_paths() -> Vec<String> {
+    vec![std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".to_string())]
+}
+
+impl ExclusionPolicy {
+    /// Reads the policy from `path`, falling back to the defaults if the file does not exist yet.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("Invalid exclusion rules in {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Validates every rule and builds the matcher used during traversal.
+    pub fn compile(&self) -> Result<ExclusionMatcher, String> {
+        let mut names = HashSet::new();
+        let mut folders = HashSet::new();
+        let mut explorer_rules = Vec::new();
+        let mut paths = Vec::new();
+        let mut path_globs = GlobSetBuilder::new();
+        let mut name_globs = GlobSetBuilder::new();
+        let mut regexes = Vec::new();
+
+        for rule in &self.rules {
+            match rule {
+                ExclusionRule::Name(name) => {
+                    names.insert(name.to_lowercase());
+                }
+                ExclusionRule::Path(path) => {
+                    if !Path::new(path).is_absolute() {
+                        return Err(format!("Exclusion path must be absolute: {}", path));
+                    }
+                    paths.push(path_key(Path::new(path)).into_owned());
+                }
+                ExclusionRule::Glob(pattern) => {
+                    let glob = Glob::new(pattern)
+                        .map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?;
+                    if pattern.contains('/') {
+                        path_globs.add(glob);
+                    } else {
+                        name_globs.add(glob);
+                    }
+                }
+                ExclusionRule::Regex(pattern) => regexes.push(pattern.clone()),
+                ExclusionRule::Folder(name) => {
+                    folders.insert(name.clone());
+                }
+                ExclusionRule::ExplorerOnly(rule) => explorer_rules.push(explorer_rule(rule)),
+            }
+        }
+        let explorer = if explorer_rules.is_empty() {
+            None
+        } else {
+            Some(Box::new(ExclusionPolicy { rules: explorer_rules }.compile()?))
+        };
+
+        let build_err = |e: globset::Error| format!("Invalid glob: {}", e);
+        Ok(ExclusionMatcher {
+            policy: self.clone(),
+            names,
+            folders,
+            paths,
+            path_globs: path_globs.build().map_err(build_err)?,
+            name_globs: name_globs.build().map_err(build_err)?,
+            regexes: RegexSet::new(&regexes).map_err(|e| format!("Invalid regex: {}", e))?,
+            explorer,
+        })
+    }
+}
+
+/// Compiled form of an `ExclusionPolicy`. Cheap to query from many threads.
+#[derive(Clone, Debug)]
+pub struct ExclusionMatcher {
+    policy: ExclusionPolicy,
+    names: HashSet<String>,
+    folders: HashSet<String>,
+    paths: Vec<PathBuf>,
+    path_globs: GlobSet,
+    name_globs: GlobSet,
+    regexes: RegexSet,
+    /// Rules wrapped in `ExplorerOnly`.
+    explorer: Option<Box<ExclusionMatcher>>,
+}
+
+impl ExclusionMatcher {
+    pub fn policy(&self) -> &ExclusionPolicy {
+        &self.policy
+    }
+
+    /// Whether scans skip `path`. `is_dir` decides whether `Folder` rules apply.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        if let Some(name) = path.file_name() {
+            let name = name.to_string_lossy();
+            if self.names.contains(&name.to_lowercase()) || self.name_globs.is_match(name.as_ref()) {
+                return true;
+            }
+            if is_dir && self.folders.contains(name.as_ref()) {
+                return true;
+            }
+        }
+
+        if !self.paths.is_empty() {
+            let key = path_key(path);
+            if self.paths.iter().any(|p| key.starts_with(p)) {
+                return true;
+            }
+        }
+
+        if self.path_globs.is_match(path) {
+            return true;
+        }
+
+        !self.regexes.is_empty() && self.regexes.is_match(&path.to_string_lossy())
+    }
+
+    /// Whether the explorer hides `path`: everything scans skip, plus `ExplorerOnly` rules.
+    pub fn is_hidden_in_explorer(&self, path: &Path, is_dir: bool) -> bool {
+        self.is_excluded(path, is_dir)
+            || self.explorer.as_ref().is_some_and(|explorer| explorer.is_excluded(path, is_dir))
+    }
+}
+
+/// `path` as `Path` rules compare it: case-insensitively on Windows, where `SystemRoot`
+/// may be `C:\WINDOWS` while the walk finds `C:\Windows`.
+fn path_key(path: &Path) -> Cow<'_, Path> {
+    if cfg!(windows) {
+        Cow::Owned(PathBuf::from(path.to_string_lossy().to_lowercase()))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// The rule inside any number of `ExplorerOnly` wrappers.
+fn explorer_rule(rule: &ExclusionRule) -> ExclusionRule {
+    match rule {
+        ExclusionRule::ExplorerOnly(inner) => explorer_rule(inner),
+        rule => rule.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_compile() {
+        let policy = ExclusionPolicy::default();
+        assert!(policy.compile().is_ok());
+        let json = serde_json::to_string(&policy).unwrap();
+        assert!(json.contains(r#"{"kind":"explorer_only","value":{"kind":"name","value":"Recovery"}}"#));
+        assert_eq!(serde_json::from_str::<ExclusionPolicy>(&json).unwrap(), policy);
+    }
+
+    #[test]
+    fn folder_rules_are_exact_and_skip_files() {
+        let matcher = ExclusionPolicy::default().compile().unwrap();
+        assert!(matcher.is_excluded(Path::new("/home/me/project/build"), true));
+        assert!(!matcher.is_excluded(Path::new("/home/me/project/Build"), true));
+        assert!(!matcher.is_excluded(Path::new("/home/me/project/build"), false));
+        assert!(!matcher.is_excluded(Path::new("/home/me/project/build.pdf"), false));
+        // Name rules still ignore case
+        assert!(matcher.is_excluded(Path::new("/home/me/.trash"), true));
+    }
+
+    #[test]
+    fn explorer_only_rules_do_not_affect_scans() {
+        let matcher = ExclusionPolicy::default().compile().unwrap();
+        for path in ["/mnt/usb/Recovery", "/mnt/usb/found.000", r"D:\found.001"] {
+            assert!(!matcher.is_excluded(Path::new(path), true));
+            assert!(matcher.is_hidden_in_explorer(Path::new(path), true));
+        }
+        assert!(!matcher.is_hidden_in_explorer(Path::new("/mnt/usb/found.txt"), false));
+        assert!(!matcher.is_hidden_in_explorer(Path::new("/mnt/usb/lost-and-found.000"), true));
+        // Anything scans skip is hidden too
+        assert!(matcher.is_hidden_in_explorer(Path::new("/mnt/usb/node_modules"), true));
+    }
+
+    #[test]
+    fn path_rules_cover_everything_below() {
+        let root = std::env::temp_dir().join("dedupe-excluded");
+        let matcher = ExclusionPolicy { rules: vec![ExclusionRule::Path(root.to_string_lossy().into_owned())] }
+            .compile()
+            .unwrap();
+        assert!(matcher.is_excluded(&root, true));
+        assert!(matcher.is_excluded(&root.join("a/b.pdf"), false));
+        assert!(!matcher.is_excluded(&std::env::temp_dir().join("dedupe-excluded-not"), true));
+        assert!(ExclusionPolicy { rules: vec![ExclusionRule::Path("relative".into())] }.compile().is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::fmt::Write;
+
+/// Content hash used for the partial and full passes. Cached hashes are only reused
+/// when they were produced by the same algorithm.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    /// Cryptographic and fast; the long-standing default.
+    #[default]
+    Blake3,
+    /// 128-bit XXH3. Fastest, but not collision resistant; meant for throwaway scans.
+    Xxh3,
+    /// SHA-256, for comparing against external manifests.
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Name stored in the cache and accepted by the CLI.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "blake3" => Some(HashAlgorithm::Blake3),
+            "xxh3" => Some(HashAlgorithm::Xxh3),
+            "sha256" => Some(HashAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn hasher(&self) -> Box<dyn ContentHasher> {
+        match self {
+            HashAlgorithm::Blake3 => Box::new(blake3::Hasher::new()),
+            HashAlgorithm::Xxh3 => Box::new(xxhash_rust::xxh3::Xxh3::new()),
+            HashAlgorithm::Sha256 => Box::new(sha2::Sha256::new()),
+        }
+    }
+}
+
+/// Incremental hasher fed by the scanner's read loops.
+pub trait ContentHasher: Send {
+    fn update(&mut self, data: &[u8]);
+    /// Lowercase hex digest.
+    fn finalize_hex(self: Box<Self>) -> String;
+    /// Digest of everything fed so far, without consuming the hasher.
+    fn snapshot_hex(&self) -> String;
+}
+
+impl ContentHasher for blake3::Hasher {
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finalize_hex(self: Box<Self>) -> String {
+        self.finalize().to_hex().to_string()
+    }
+
+    fn snapshot_hex(&self) -> String {
+        self.finalize().to_hex().to_string()
+    }
+}
+
+impl ContentHasher for xxhash_rust::xxh3::Xxh3 {
+    fn update(&mut self, data: &[u8]) {
+        xxhash_rust::xxh3::Xxh3::update(self, data);
+    }
+
+    fn finalize_hex(self: Box<Self>) -> String {
+        format!("{:032x}", self.digest128())
+    }
+
+    fn snapshot_hex(&self) -> String {
+        format!("{:032x}", self.digest128())
+    }
+}
+
+impl ContentHasher for sha2::Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        Digest::update(self, data);
+    }
+
+    fn finalize_hex(self: Box<Self>) -> String {
+        to_hex(&self.finalize())
+    }
+
+    fn snapshot_hex(&self) -> String {
+        to_hex(&self.clone().finalize())
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How the hashing passes open and read files. The default reads at full speed and
+/// leaves atime and the page cache to the OS.
+#[derive(Clone, Default)]
+pub struct IoPolicy {
+    /// Avoid touching atime and drop each file from the page cache once it is hashed.
+    background: bool,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl IoPolicy {
+    /// `max_bytes_per_sec` caps the combined read rate of every thread sharing this policy.
+    pub fn new(background: bool, max_bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            background,
+            limiter: max_bytes_per_sec.filter(|&rate| rate > 0).map(|rate| Arc::new(RateLimiter::new(rate))),
+        }
+    }
+
+    /// Memory-mapped reads can be neither throttled nor kept out of the page cache.
+    pub fn allows_mmap(&self) -> bool {
+        !self.background && self.limiter.is_none()
+    }
+
+    /// Opens `path` for reading. In background mode this asks for `O_NOATIME`, which is only
+    /// allowed on files the user owns, and falls back to a plain open otherwise.
+    pub fn open(&self, path: impl AsRef<Path>) -> io::Result<File> {
+        #[cfg(target_os = "linux")]
+        if self.background {
+            use std::os::unix::fs::OpenOptionsExt;
+            match std::fs::OpenOptions::new().read(true).custom_flags(libc::O_NOATIME).open(path.as_ref()) {
+                Err(e) if e.raw_os_error() == Some(libc::EPERM) => {}
+                result => return result,
+            }
+        }
+        File::open(path)
+    }
+
+    /// Records `bytes` just read, sleeping as needed to stay under the rate cap.
+    pub fn consume(&self, bytes: usize) {
+        if let Some(limiter) = &self.limiter {
+            limiter.consume(bytes as u64);
+        }
+    }
+
+    /// Called once a file has been hashed. In background mode its pages are dropped from
+    /// the page cache, so a scan doesn't evict what other programs are using.
+    pub fn finish(&self, file: &File) {
+        #[cfg(target_os = "linux")]
+        if self.background {
+            use std::os::unix::io::AsRawFd;
+            // SAFETY: plain advisory call on an open descriptor; failure is harmless.
+            unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = file;
+    }
+}
+
+/// Shared byte budget: every read is scheduled at `bytes read so far / rate` after the start.
+struct RateLimiter {
+    bytes_per_sec: u64,
+    /// Start of the current accounting window and bytes read since.
+    window: Mutex<(Instant, u64)>,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self { bytes_per_sec, window: Mutex::new((Instant::now(), 0)) }
+    }
+
+    fn consume(&self, bytes: u64) {
+        let wait = {
+            let mut window = self.window.lock().unwrap();
+            let now = Instant::now();
+            let due = window.0 + Duration::from_secs_f64(window.1 as f64 / self.bytes_per_sec as f64);
+            // After an idle stretch (e.g. cached files) start over instead of allowing a burst
+            if due + Duration::from_secs(1) < now {
+                *window = (now, 0);
+            }
+            window.1 += bytes;
+            let due = window.0 + Duration::from_secs_f64(window.1 as f64 / self.bytes_per_sec as f64);
+            due.saturating_duration_since(now)
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+/// Lowers the CPU and I/O priority of the calling thread: nice 19 and the lowest
+/// best-effort I/O class, which still makes progress when the disk is busy (unlike idle).
+/// Meant for the threads of a dedicated pool, since the change can't be undone without privileges.
+#[cfg(target_os = "linux")]
+pub fn lower_current_thread_priority() {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_BE: libc::c_int = 2;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    // SAFETY: both calls only take integers; `who == 0` targets the calling thread on Linux.
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, 19);
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn lower_current_thread_priority() {}
+
+/// A thread pool whose workers run at background priority. Hashing inside
+/// `pool.install` (including the per-device scheduler) uses these threads.
+pub fn background_pool() -> Result<rayon::ThreadPool, rayon::ThreadPoolBuildError> {
+    rayon::ThreadPoolBuilder::new()
+        .thread_name(|i| format!("dedupe-background-{}", i))
+        .start_handler(|_| lower_current_thread_priority())
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_root, write};
+
+    #[test]
+    fn mmap_only_without_throttling_or_background() {
+        assert!(IoPolicy::default().allows_mmap());
+        assert!(IoPolicy::new(false, Some(0)).allows_mmap());
+        assert!(!IoPolicy::new(false, Some(1024)).allows_mmap());
+        assert!(!IoPolicy::new(true, None).allows_mmap());
+    }
+
+    #[test]
+    fn background_reads_see_the_whole_file() {
+        let (_dir, root) = temp_root();
+        let path = write(&root, "a.pdf", b"background contents");
+        let io = IoPolicy::new(true, None);
+        let mut file = io.open(&path).unwrap();
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(&mut file, &mut contents).unwrap();
+        io.finish(&file);
+        assert_eq!(contents, b"background contents");
+    }
+
+    #[test]
+    fn rate_limiter_paces_reads() {
+        let limiter = RateLimiter::new(10_000);
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.consume(1_000);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(450), "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn rate_limiter_does_not_burst_after_idling() {
+        let limiter = RateLimiter::new(10_000);
+        *limiter.window.lock().unwrap() = (Instant::now() - Duration::from_secs(10), 0);
+        let start = Instant::now();
+        limiter.consume(1_000);
+        assert!(start.elapsed() >= Duration::from_millis(90), "{:?}", start.elapsed());
+    }
+}
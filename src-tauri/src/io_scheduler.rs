@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Order in which files queued on the same device are read.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadOrder {
+    /// As found during traversal.
+    #[default]
+    Discovery,
+    /// By inode number, which roughly follows allocation order on most file systems.
+    Inode,
+    /// By the disk offset of each file's first extent (FIEMAP on Linux), falling back to
+    /// the inode number where that isn't available. Gives near-sequential reads on HDDs.
+    Physical,
+}
+
+/// How many reads may hit one device at the same time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceKind {
+    /// Spinning disk: concurrent reads only add seeks, so files are read one at a time.
+    Rotational,
+    /// SSD, NVMe, network or unknown storage: reads run as wide as the thread pool.
+    SolidState,
+}
+
+impl DeviceKind {
+    pub fn concurrency(&self) -> usize {
+        match self {
+            DeviceKind::Rotational => 1,
+            DeviceKind::SolidState => rayon::current_num_threads().max(1),
+        }
+    }
+}
+
+/// Looks up whether the block device behind `dev` (as in `st_dev`) is rotational,
+/// using `/sys/dev/block/<major>:<minor>`. Partitions inherit their disk's setting.
+#[cfg(target_os = "linux")]
+pub fn device_kind(dev: u64) -> DeviceKind {
+    let base = format!("/sys/dev/block/{}:{}", libc::major(dev), libc::minor(dev));
+    let rotational = ["queue/rotational", "../queue/rotational"].iter()
+        .find_map(|rel| std::fs::read_to_string(format!("{}/{}", base, rel)).ok());
+
+    match rotational.as_deref().map(str::trim) {
+        Some("1") => DeviceKind::Rotational,
+        _ => DeviceKind::SolidState,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn device_kind(_dev: u64) -> DeviceKind {
+    DeviceKind::SolidState
+}
+
+/// The whole disk behind `dev` (as in `st_dev`), so partitions of one spinning disk share
+/// a queue. Partitions resolve to their parent disk, and device-mapper volumes (LVM, LUKS)
+/// built on a single device to that device's disk. Anything else is its own disk.
+#[cfg(target_os = "linux")]
+pub fn physical_disk(dev: u64) -> u64 {
+    let Ok(mut node) = std::fs::canonicalize(format!("/sys/dev/block/{}:{}", libc::major(dev), libc::minor(dev))) else {
+        return dev;
+    };
+    // Bounded, since each step only moves down the stack of block devices
+    for _ in 0..8 {
+        if node.join("partition").exists() {
+            match node.parent() {
+                Some(disk) => node = disk.to_path_buf(),
+                None => break,
+            }
+        }
+        let slaves: Vec<std::path::PathBuf> = std::fs::read_dir(node.join("slaves"))
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        match slaves.as_slice() {
+            [slave] => match std::fs::canonicalize(slave) {
+                Ok(slave) => node = slave,
+                Err(_) => break,
+            },
+            _ => break,
+        }
+    }
+
+    let numbers = std::fs::read_to_string(node.join("dev")).ok();
+    let parsed = numbers.as_deref()
+        .and_then(|numbers| numbers.trim().split_once(':'))
+        .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)));
+    match parsed {
+        Some((major, minor)) => libc::makedev(major, minor),
+        None => dev,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn physical_disk(dev: u64) -> u64 {
+    dev
+}
+
+/// Physical byte offset of the first extent of `path`, via the `FS_IOC_FIEMAP` ioctl.
+/// `None` for empty or inline files, and on file systems without FIEMAP support.
+#[cfg(target_os = "linux")]
+pub fn physical_offset(path: &Path) -> Option<u64> {
+    use std::os::unix::io::AsRawFd;
+
+    // struct fiemap followed by a single struct fiemap_extent (linux/fiemap.h)
+    #[repr(C)]
+    #[derive(Default)]
+    struct Fiemap {
+        fm_start: u64,
+        fm_length: u64,
+        fm_flags: u32,
+        fm_mapped_extents: u32,
+        fm_extent_count: u32,
+        fm_reserved: u32,
+        fe_logical: u64,
+        fe_physical: u64,
+        fe_length: u64,
+        fe_reserved64: [u64; 2],
+        fe_flags: u32,
+        fe_reserved: [u32; 3],
+    }
+    const FS_IOC_FIEMAP: libc::c_ulong = 0xC020_660B;
+
+    let file = std::fs::File::open(path).ok()?;
+    let mut map = Fiemap {
+        fm_length: u64::MAX,
+        fm_extent_count: 1,
+        ..Fiemap::default()
+    };
+    // SAFETY: `map` is a valid fiemap header with room for exactly `fm_extent_count` extents.
+    let rc = unsafe { libc::ioctl(file.as_raw_fd(), FS_IOC_FIEMAP as _, &mut map as *mut Fiemap) };
+    (rc == 0 && map.fm_mapped_extents > 0).then_some(map.fe_physical)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn physical_offset(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Runs I/O-bound work grouped by the disk it reads from, with a separate concurrency
+/// limit per disk. Disks are processed at the same time, so a slow USB disk
+/// doesn't hold back an SSD, and the USB disk isn't flooded with random reads.
+/// Partitions of the same disk share one queue.
+pub struct DeviceScheduler {
+    /// Disk and its kind, by `st_dev`.
+    disks: Mutex<HashMap<u64, (u64, DeviceKind)>>,
+}
+
+impl Default for DeviceScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceScheduler {
+    pub fn new() -> Self {
+        Self { disks: Mutex::new(HashMap::new()) }
+    }
+
+    fn disk(&self, dev: u64) -> (u64, DeviceKind) {
+        *self.disks.lock().unwrap().entry(dev).or_insert_with(|| {
+            let disk = physical_disk(dev);
+            (disk, device_kind(disk))
+        })
+    }
+
+    /// Applies `work` to every item and returns the results in no particular order.
+    pub fn run<T, R, D, W>(&self, items: Vec<T>, device_of: D, work: W) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        D: Fn(&T) -> u64,
+        W: Fn(T) -> R + Sync,
+    {
+        let mut queues: HashMap<u64, (DeviceKind, Vec<T>)> = HashMap::new();
+        for item in items {
+            let (disk, kind) = self.disk(device_of(&item));
+            queues.entry(disk).or_insert_with(|| (kind, Vec::new())).1.push(item);
+        }
+
+        let results = Mutex::new(Vec::new());
+        rayon::scope(|scope| {
+            for (kind, mut queue) in queues.into_values() {
+                // Workers pop from the end; reverse so items keep their given order per disk
+                queue.reverse();
+                let workers = kind.concurrency().min(queue.len());
+                let queue = Arc::new(Mutex::new(queue));
+
+                for _ in 0..workers {
+                    let queue = queue.clone();
+                    let (work, results) = (&work, &results);
+                    scope.spawn(move |_| loop {
+                        let next = queue.lock().unwrap().pop();
+                        match next {
+                            Some(item) => {
+                                let result = work(item);
+                                results.lock().unwrap().push(result);
+                            }
+                            None => break,
+                        }
+                    });
+                }
+            }
+        });
+        results.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn partitions_resolve_to_their_disk() {
+        let Ok(devices) = std::fs::read_dir("/sys/class/block") else { return };
+        let number = |node: &Path| -> Option<u64> {
+            let numbers = std::fs::read_to_string(node.join("dev")).ok()?;
+            let (major, minor) = numbers.trim().split_once(':')?;
+            Some(libc::makedev(major.parse().ok()?, minor.parse().ok()?))
+        };
+        for device in devices.flatten() {
+            let Ok(node) = std::fs::canonicalize(device.path()) else { continue };
+            let has_slaves = std::fs::read_dir(node.join("slaves")).is_ok_and(|mut s| s.next().is_some());
+            let (Some(dev), false) = (number(&node), has_slaves) else { continue };
+            let expected = if node.join("partition").exists() {
+                number(node.parent().unwrap()).unwrap()
+            } else {
+                dev
+            };
+            assert_eq!(physical_disk(dev), expected, "{}", node.display());
+        }
+    }
+
+    #[test]
+    fn unknown_devices_are_their_own_disk() {
+        let unknown = u64::MAX - 1;
+        assert_eq!(physical_disk(unknown), unknown);
+    }
+
+    #[test]
+    fn run_returns_every_result() {
+        let scheduler = DeviceScheduler::new();
+        let mut results = scheduler.run((0..100u64).collect(), |i| i % 3, |i| i * 2);
+        results.sort();
+        assert_eq!(results, (0..100u64).map(|i| i * 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::cancel::CancellationToken;
+use crate::engine::{ProgressPayload, ScanResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Finished jobs kept around for `get_scan_result`. Older ones are dropped first.
+const MAX_FINISHED_JOBS: usize = 10;
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Cancelled,
+    /// The scan stopped on an unexpected error and left no result.
+    Failed { message: String },
+}
+
+/// Lightweight view of a job, without its (potentially large) result.
+#[derive(Serialize, Clone, Debug)]
+pub struct JobSummary {
+    pub id: String,
+    pub paths: Vec<String>,
+    pub status: JobStatus,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub progress: Option<ProgressPayload>,
+    pub group_count: Option<usize>,
+}
+
+struct ScanJob {
+    seq: u64,
+    summary: JobSummary,
+    cancel: CancellationToken,
+    result: Option<ScanResult>,
+}
+
+/// Tracks running and recently finished scans by ID.
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: HashMap<String, ScanJob>,
+    next_id: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl JobRegistry {
+    /// Registers a new running job and returns its ID and cancellation token.
+    pub fn create(&mut self, paths: Vec<String>) -> (String, CancellationToken) {
+        self.next_id += 1;
+        let started_at = now_secs();
+        let id = format!("scan-{}-{}", started_at, self.next_id);
+        let cancel = CancellationToken::new();
+
+        self.jobs.insert(id.clone(), ScanJob {
+            seq: self.next_id,
+            summary: JobSummary {
+                id: id.clone(),
+                paths,
+                status: JobStatus::Running,
+                started_at,
+                finished_at: None,
+                progress: None,
+                group_count: None,
+            },
+            cancel: cancel.clone(),
+            result: None,
+        });
+        (id, cancel)
+    }
+
+    pub fn set_progress(&mut self, id: &str, progress: ProgressPayload) {
+        if let Some(job) = self.jobs.get_mut(id) {
+            job.summary.progress = Some(progress);
+        }
+    }
+
+    /// Stores the result and marks the job completed or cancelled.
+    pub fn finish(&mut self, id: &str, result: ScanResult) {
+        if let Some(job) = self.jobs.get_mut(id) {
+            job.summary.status = if result.cancelled { JobStatus::Cancelled } else { JobStatus::Completed };
+            job.summary.finished_at = Some(now_secs());
+            job.summary.group_count = Some(result.groups.len());
+            job.result = Some(result);
+        }
+        self.prune_finished();
+    }
+
+    /// Marks a job that ended without a result, e.g. because the engine panicked.
+    pub fn fail(&mut self, id: &str, message: String) {
+        if let Some(job) = self.jobs.get_mut(id) {
+            job.summary.status = JobStatus::Failed { message };
+            job.summary.finished_at = Some(now_secs());
+        }
+        self.prune_finished();
+    }
+
+    /// Requests cancellation. Returns false if the job is unknown or already finished.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.jobs.get(id) {
+            Some(job) if job.summary.status == JobStatus::Running => {
+                job.cancel.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn status(&self, id: &str) -> Option<JobSummary> {
+        self.jobs.get(id).map(|job| job.summary.clone())
+    }
+
+    /// Returns the result of a finished job, or `None` while it is still running.
+    pub fn result(&self, id: &str) -> Option<&ScanResult> {
+        self.jobs.get(id).and_then(|job| job.result.as_ref())
+    }
+
+    /// All known jobs, newest first.
+    pub fn list(&self) -> Vec<JobSummary> {
+        let mut jobs: Vec<&ScanJob> = self.jobs.values().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.seq));
+        jobs.into_iter().map(|job| job.summary.clone()).collect()
+    }
+
+    fn prune_finished(&mut self) {
+        let mut finished: Vec<(u64, String)> = self.jobs.values()
+            .filter(|job| job.summary.status != JobStatus::Running)
+            .map(|job| (job.seq, job.summary.id.clone()))
+            .collect();
+
+        if finished.len() <= MAX_FINISHED_JOBS { return; }
+
+        finished.sort();
+        let excess = finished.len() - MAX_FINISHED_JOBS;
+        for (_, id) in finished.into_iter().take(excess) {
+            self.jobs.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_job_is_finished_without_result() {
+        let mut jobs = JobRegistry::default();
+        let (id, _) = jobs.create(vec!["/data".into()]);
+        jobs.fail(&id, "boom".into());
+
+        let summary = jobs.status(&id).unwrap();
+        assert_eq!(summary.status, JobStatus::Failed { message: "boom".into() });
+        assert!(summary.finished_at.is_some());
+        assert!(jobs.result(&id).is_none());
+        assert!(!jobs.cancel(&id));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::scanner::file_identity;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A requested root that was not walked because another root already covers it.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct MergedRoot {
+    /// The path as it was requested.
+    pub path: String,
+    /// The scanned root that contains it (or is the same directory).
+    pub merged_into: String,
+}
+
+struct Root {
+    requested: String,
+    canonical: PathBuf,
+    /// (device, inode) of the root and each of its ancestors, root first.
+    /// Empty when the platform provides no inode numbers.
+    identities: Vec<(u64, u64)>,
+}
+
+impl Root {
+    fn identity(&self) -> Option<(u64, u64)> {
+        self.identities.first().copied()
+    }
+
+    /// Whether `self` is the same directory as `other` or lies below it.
+    fn is_within(&self, other: &Root) -> bool {
+        if self.canonical.starts_with(&other.canonical) {
+            return true;
+        }
+        other.identity().is_some_and(|id| self.identities.contains(&id))
+    }
+}
+
+fn identity_of(path: &Path) -> Option<(u64, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let (dev, ino, _) = file_identity(&metadata);
+    (ino != 0).then_some((dev, ino))
+}
+
+/// Canonicalizes the requested roots and drops any root that is identical to, or nested
+/// inside, another one, so no file is walked twice.
+///
+/// Roots are compared by canonical path and by the (device, inode) of each of their
+/// ancestors, which also catches the same directory reached through a bind mount.
+/// When two roots are the same directory, the first one requested is kept.
+/// Roots that cannot be canonicalized are kept as given so the walk can report the error.
+pub fn normalize_roots(paths: &[String]) -> (Vec<String>, Vec<MergedRoot>) {
+    let roots: Vec<Root> = paths.iter()
+        .map(|requested| {
+            let canonical = std::fs::canonicalize(requested).unwrap_or_else(|_| PathBuf::from(requested));
+            let identities = match identity_of(&canonical) {
+                Some(_) => canonical.ancestors().filter_map(identity_of).collect(),
+                None => Vec::new(),
+            };
+            Root { requested: requested.clone(), canonical, identities }
+        })
+        .collect();
+
+    // The root that directly covers each root, if any
+    let covered_by: Vec<Option<usize>> = (0..roots.len())
+        .map(|i| {
+            (0..roots.len()).find(|&j| {
+                if i == j || !roots[i].is_within(&roots[j]) {
+                    return false;
+                }
+                // Same directory both ways: only the earlier request covers the later one
+                !roots[j].is_within(&roots[i]) || j < i
+            })
+        })
+        .collect();
+
+    let mut scan_roots = Vec::new();
+    let mut merged = Vec::new();
+    for (i, root) in roots.iter().enumerate() {
+        // Follow the chain up to a root that is actually walked
+        let mut container = covered_by[i];
+        let mut steps = 0;
+        while let Some(next) = container.and_then(|k| covered_by[k]) {
+            steps += 1;
+            if steps > roots.len() { break; }
+            container = Some(next);
+        }
+
+        match container {
+            Some(k) if steps <= roots.len() => merged.push(MergedRoot {
+                path: root.requested.clone(),
+                merged_into: roots[k].canonical.to_string_lossy().into_owned(),
+            }),
+            _ => scan_roots.push(root.canonical.to_string_lossy().into_owned()),
+        }
+    }
+    (scan_roots, merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{path_string, temp_root};
+
+    #[test]
+    fn merges_nested_and_repeated_roots() {
+        let (_dir, parent) = temp_root();
+        let child = parent.join("child");
+        let other = parent.join("other");
+        std::fs::create_dir_all(child.join("deeper")).unwrap();
+        std::fs::create_dir(&other).unwrap();
+
+        let requested = [child.join("deeper"), child.clone(), other.clone(), child.clone()].map(|p| path_string(&p));
+        let (roots, merged) = normalize_roots(&requested);
+        assert_eq!(roots, [path_string(&child), path_string(&other)]);
+        assert_eq!(merged, [
+            MergedRoot { path: requested[0].clone(), merged_into: path_string(&child) },
+            MergedRoot { path: requested[3].clone(), merged_into: path_string(&child) },
+        ]);
+
+        let (roots, merged) = normalize_roots(&[path_string(&child), path_string(&parent)]);
+        assert_eq!(roots, [path_string(&parent)]);
+        assert_eq!(merged[0].merged_into, path_string(&parent));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn merges_root_reached_through_symlink() {
+        let (_dir, root) = temp_root();
+        let real = root.join("real");
+        let link = root.join("link");
+        std::fs::create_dir(&real).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let (roots, merged) = normalize_roots(&[path_string(&real), path_string(&link)]);
+        assert_eq!(roots, [path_string(&real)]);
+        assert_eq!(merged, [MergedRoot { path: path_string(&link), merged_into: path_string(&real) }]);
+    }
+
+    #[test]
+    fn keeps_missing_roots_as_given() {
+        let (_dir, root) = temp_root();
+        let missing = path_string(&root.join("missing"));
+        let (roots, merged) = normalize_roots(std::slice::from_ref(&missing));
+        assert_eq!(roots, [missing]);
+        assert!(merged.is_empty());
+    }
+}
@@ -1,10 +1,19 @@
 use std::fs::File;
 use std::io::{Read, BufReader};
-use blake3::Hasher;
+use crate::cancel::CancellationToken;
+use crate::content::{self, ContentDetection, FileCategory};
+use crate::empty::DirectoryContents;
+use crate::engine::ScanConfig;
+use crate::errors::{ErrorLog, HashError, ScanError, ScanPhase};
+use crate::hashing::{ContentHasher, HashAlgorithm};
+use crate::io_policy::IoPolicy;
+use rayon::prelude::*;
 
 use serde::Serialize;
 use std::time::SystemTime;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::io::Seek;
 use std::io::SeekFrom;
 
@@ -13,128 +22,494 @@ pub struct FileMetadata {
     pub path: String,
     pub size: u64,
     pub modified: u64,
+    /// Modification and status-change times in nanoseconds, for cache validation only.
+    /// Not serialized: JavaScript numbers can't hold them exactly.
+    #[serde(skip_serializing)]
+    pub mtime_ns: i64,
+    #[serde(skip_serializing)]
+    pub ctime_ns: i64,
     pub partial_hash: Option<String>,
     pub full_hash: Option<String>,
+    /// Only set when the type was sniffed from the file's bytes.
+    pub mime_type: Option<String>,
+    pub category: FileCategory,
+    /// Device and inode identify the underlying file; 0 where the platform doesn't expose them.
+    pub dev: u64,
+    pub inode: u64,
+    /// Total hard links to the inode, including ones outside the scanned roots.
+    pub nlink: u64,
+    /// Other scanned paths that are hard links to this same file.
+    pub hard_links: Vec<String>,
 }
 
+impl FileMetadata {
+    /// A file as found by traversal, before any hashing.
+    pub fn new(path: String, metadata: &std::fs::Metadata, category: FileCategory) -> Self {
+        let (dev, inode, nlink) = file_identity(metadata);
+        let (mtime_ns, ctime_ns) = file_times(metadata);
+        Self {
+            path,
+            size: metadata.len(),
+            modified: modified_secs(metadata),
+            mtime_ns,
+            ctime_ns,
+            partial_hash: None,
+            full_hash: None,
+            mime_type: None,
+            category,
+            dev,
+            inode,
+            nlink,
+            hard_links: Vec::new(),
+        }
+    }
+}
+
+/// Returns `(dev, inode, nlink)` for a file.
+#[cfg(unix)]
+pub fn file_identity(metadata: &std::fs::Metadata) -> (u64, u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino(), metadata.nlink())
+}
+
+#[cfg(not(unix))]
+pub fn file_identity(_metadata: &std::fs::Metadata) -> (u64, u64, u64) {
+    (0, 0, 1)
+}
+
+/// Modification time in whole seconds since the Unix epoch, as stored in `FileMetadata`.
+pub fn modified_secs(metadata: &std::fs::Metadata) -> u64 {
+    metadata.modified()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// `(mtime, ctime)` in nanoseconds since the epoch. Unlike the mtime, the ctime can't be set
+/// by tools such as `touch` or `rsync -t`, so it catches rewrites that restore the old mtime.
+#[cfg(unix)]
+pub fn file_times(metadata: &std::fs::Metadata) -> (i64, i64) {
+    use std::os::unix::fs::MetadataExt;
+    (
+        metadata.mtime() * 1_000_000_000 + metadata.mtime_nsec(),
+        metadata.ctime() * 1_000_000_000 + metadata.ctime_nsec(),
+    )
+}
+
+/// `(mtime, 0)`: there is no ctime to compare outside Unix.
+#[cfg(not(unix))]
+pub fn file_times(metadata: &std::fs::Metadata) -> (i64, i64) {
+    let mtime = metadata.modified().ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0);
+    (mtime, 0)
+}
+
+/// A file whose size or modification time changed between traversal and hashing.
+/// Its hashes may mix old and new contents, so it is neither grouped nor cached.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct UnstableFile {
+    pub path: String,
+    /// Size and mtime seen during traversal.
+    pub size: u64,
+    pub modified: u64,
+    /// Size and mtime after hashing; `None` when the file was removed.
+    pub current_size: Option<u64>,
+    pub current_modified: Option<u64>,
+}
+
+impl UnstableFile {
+    /// Re-stats a hashed file. Returns `None` while it still matches what traversal saw.
+    pub fn check(file: &FileMetadata) -> Option<Self> {
+        let current = std::fs::metadata(&file.path).ok();
+        let current_size = current.as_ref().map(|m| m.len());
+        let current_modified = current.as_ref().map(modified_secs);
+        let current_mtime_ns = current.as_ref().map(|m| file_times(m).0);
+        if current_size == Some(file.size) && current_mtime_ns == Some(file.mtime_ns) {
+            return None;
+        }
+        Some(Self {
+            path: file.path.clone(),
+            size: file.size,
+            modified: file.modified,
+            current_size,
+            current_modified,
+        })
+    }
+}
+
+/// A symlink followed during the scan. Its target is what appears in the results
+/// (or was already walked), so the link itself is never reported as a duplicate.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SymlinkAlias {
+    pub path: String,
+    pub target: String,
+}
+
+/// Directories already walked in this scan, keyed by (device, inode). Shared by every
+/// root so a folder reachable through several symlinks is read once and loops end.
+#[derive(Clone, Default)]
+pub struct VisitedDirs {
+    dirs: Arc<Mutex<HashMap<(u64, u64), WalkedDir>>>,
+}
+
+/// The path a directory is walked under, and whether that path is a symlink.
+struct WalkedDir {
+    path: String,
+    via_link: bool,
+}
+
+impl VisitedDirs {
+    /// Marks `path` as visited. Returns the path it is already walked under, if any.
+    ///
+    /// A real directory always wins over a symlink to it: it is walked even if a link got
+    /// there first, since files found through the link are reported under their real paths.
+    fn visit(&self, path: &Path, via_link: bool) -> Option<String> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let (dev, inode, _) = file_identity(&metadata);
+        if inode == 0 { return None; }
+
+        let mut dirs = self.dirs.lock().unwrap();
+        match dirs.get(&(dev, inode)) {
+            Some(first) if via_link || !first.via_link => Some(first.path.clone()),
+            _ => {
+                dirs.insert((dev, inode), WalkedDir { path: path.to_string_lossy().into_owned(), via_link });
+                None
+            }
+        }
+    }
+}
+
+/// Symlinked folders being walked, with their real paths. Entries found below a link
+/// are reported under the real path, so a file never shows up under two names.
+#[derive(Clone, Default)]
+struct FollowedLinks {
+    links: Arc<Mutex<Vec<(PathBuf, PathBuf)>>>,
+}
 
-pub fn get_partial_hash(path: &str) -> Option<String> {
+impl FollowedLinks {
+    fn insert(&self, link: PathBuf, target: PathBuf) {
+        self.links.lock().unwrap().push((link, target));
+    }
+
+    /// `path` with the deepest followed link above it replaced by its target.
+    fn resolve(&self, path: &Path) -> Option<PathBuf> {
+        let links = self.links.lock().unwrap();
+        links.iter()
+            .filter(|(link, _)| path != link && path.starts_with(link))
+            .max_by_key(|(link, _)| link.components().count())
+            .and_then(|(link, target)| path.strip_prefix(link).ok().map(|rest| target.join(rest)))
+    }
+}
+
+/// Files found under one root, plus the symlinks that were resolved along the way
+/// and the paths that could not be read.
+#[derive(Default)]
+pub struct DirectoryScan {
+    pub files: Vec<FileMetadata>,
+    pub aliases: Vec<SymlinkAlias>,
+    pub errors: Vec<ScanError>,
+    /// Zero-byte files and empty folders; only filled when `ScanConfig::find_empty` is set.
+    pub empty_files: Vec<String>,
+    pub empty_dirs: Vec<String>,
+}
+
+fn walk_error(err: &jwalk::Error, fallback: &Path, phase: ScanPhase) -> ScanError {
+    let path = err.path().unwrap_or(fallback);
+    match err.io_error() {
+        Some(io_err) => ScanError::from_io(path, phase, io_err),
+        None => ScanError::other(&path.to_string_lossy(), phase, err.to_string()),
+    }
+}
+
+
+pub fn get_partial_hash(path: &str, algorithm: HashAlgorithm, io: &IoPolicy) -> Result<String, HashError> {
     // xattr caching removed for reliability. 
     // Moving files does not update xattr, leading to stale hashes.
     // We strictly use the SQLite DB for caching now.
 
-    let mut file = File::open(path).ok()?;
-    let metadata = file.metadata().ok()?;
-    let size = metadata.len();
-    
-    if size == 0 { return None; }
+    let mut file = io.open(path)?;
+    let hash = partial_hash_of(&mut file, algorithm, io);
+    io.finish(&file);
+    hash
+}
 
-    let mut hasher = Hasher::new();
+fn partial_hash_of(file: &mut File, algorithm: HashAlgorithm, io: &IoPolicy) -> Result<String, HashError> {
+    let size = file.metadata()?.len();
+
+    let mut hasher = algorithm.hasher();
     let mut buffer = [0u8; 16384]; // 16KB
     
     // Hash the head
-    let n = file.read(&mut buffer).ok()?;
+    let head = (size as usize).min(buffer.len());
+    let n = read_up_to(file, &mut buffer[..head])?;
+    if n < head {
+        return Err(HashError::Truncated { expected: head as u64, read: n as u64 });
+    }
+    io.consume(n);
     hasher.update(&buffer[..n]);
 
     // If file is large enough, hash the tail to reduce collisions
     // This is vital for video files that share the same headers
     if size > 32768 {
-        let _ = file.seek(SeekFrom::End(-16384)).ok()?;
-        let n = file.read(&mut buffer).ok()?;
+        file.seek(SeekFrom::End(-16384))?;
+        let n = read_up_to(file, &mut buffer)?;
+        if n < buffer.len() {
+            return Err(HashError::Truncated { expected: size, read: size - 16384 + n as u64 });
+        }
+        io.consume(n);
         hasher.update(&buffer[..n]);
     }
     
+    Ok(hasher.finalize_hex())
+}
+
+/// Hashes a memory-mapped file with BLAKE3 across the rayon pool. Returns `None` if the
+/// file can't be mapped or no longer has the expected size, so the caller can stream it
+/// instead. The digest is identical to the streaming one, so cached hashes stay valid.
+fn hash_mapped_blake3(file: &File, size: u64) -> Option<String> {
+    // SAFETY: the map is read-only and dropped before returning. A file truncated by another
+    // process while mapped still raises SIGBUS, which is why mapping is opt-in.
+    let map = unsafe { memmap2::Mmap::map(file) }.ok()?;
+    if map.len() as u64 != size { return None; }
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_rayon(&map);
     Some(hasher.finalize().to_hex().to_string())
 }
 
-pub fn get_full_hash(path: &str) -> Option<String> {
+/// Full-content hash. BLAKE3 files of at least `mmap_threshold` bytes take the mapped,
+/// multithreaded path unless `io` throttles or runs in the background; everything else is streamed.
+pub fn get_full_hash(path: &str, algorithm: HashAlgorithm, mmap_threshold: Option<u64>, io: &IoPolicy) -> Result<String, HashError> {
     // xattr caching removed for reliability.
 
-    let file = File::open(path).ok()?;
-    let mut reader = BufReader::new(file);
-    let mut hasher = Hasher::new();
-    let mut buffer = [0u8; 1048576]; // 1MB buffer for NVMe/SSD optimization
-    
-    while let Ok(n) = reader.read(&mut buffer) {
-        if n == 0 { break; }
-        hasher.update(&buffer[..n]);
-    }
-    
-    let hash = hasher.finalize().to_hex().to_string();
+    let file = io.open(path)?;
+    let hash = full_hash_of(&file, algorithm, mmap_threshold, io);
+    io.finish(&file);
     
     // Cache update disabled to prevent stale data on move.
     
-    Some(hash)
+    hash
 }
 
-pub fn scan_directory(
-    path: &str, 
-    scan_hidden: bool,
-    scan_images: bool,
-    scan_videos: bool,
-    scan_zips: bool,
-    min_file_size: u64
-) -> Vec<FileMetadata> {
-
-
-    // Comprehensive Blacklist
-    let blacklist = [
-        "/System", "/Library", "/Windows", "/bin", "/usr/bin", "/usr/sbin",
-        "/dev", "/proc", "/sys", "/etc", "/var/lib", "/var/cache",
-        ".Trash", "$RECYCLE.BIN"
-    ];
-
-    // Developer / High-Entropy Folder Exclusions
-    let dev_black_names = [
-        "node_modules", "venv", ".venv", "env", "target", "dist", "build",
-        "__pycache__", ".git", ".hg", ".svn", ".vscode", ".idea"
-    ];
+fn full_hash_of(file: &File, algorithm: HashAlgorithm, mmap_threshold: Option<u64>, io: &IoPolicy) -> Result<String, HashError> {
+    let size = file.metadata()?.len();
+    if let (HashAlgorithm::Blake3, Some(threshold)) = (algorithm, mmap_threshold) {
+        if size >= threshold && io.allows_mmap() {
+            if let Some(hash) = hash_mapped_blake3(file, size) {
+                return Ok(hash);
+            }
+        }
+    }
+    stream_hash(BufReader::new(file), size, algorithm, io)
+}
 
-    // Build Whitelist dynamically (Using HashSet for O(1) lookup)
-    let mut whitelist_exts = HashSet::new();
-    
-    if scan_images {
-        for ext in ["jpg", "jpeg", "png", "gif", "webp", "heic", "tiff", "bmp", "arw", "cr2", "nef", "dng", "orf", "rw2", "svg", "psd", "ai", "ico"] {
-            whitelist_exts.insert(ext.to_string());
+/// Hashes everything `reader` yields, failing if that is less than `size` bytes.
+fn stream_hash(mut reader: impl Read, size: u64, algorithm: HashAlgorithm, io: &IoPolicy) -> Result<String, HashError> {
+    let mut hasher = algorithm.hasher();
+    let mut buffer = [0u8; 1048576]; // 1MB buffer for NVMe/SSD optimization
+    let mut total = 0u64;
+
+    loop {
+        let n = read_up_to(&mut reader, &mut buffer)?;
+        if n == 0 { break; }
+        io.consume(n);
+        hasher.update(&buffer[..n]);
+        total += n as u64;
+    }
+    if total < size {
+        return Err(HashError::Truncated { expected: size, read: total });
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// Reads up to `buf.len()` bytes, stopping early only at end of file.
+pub fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
         }
     }
-    
-    if scan_videos {
-        for ext in ["mp4", "mov", "avi", "mkv", "wmv", "flv", "webm", "m4v", "ts", "mts", "m2ts", "3gp", "divx", "vob"] {
-            whitelist_exts.insert(ext.to_string());
+    Ok(filled)
+}
+
+/// Feeds `len` bytes starting at `offset` into `hasher`. Fails if the file ends early.
+pub fn hash_range(path: &str, offset: u64, len: u64, hasher: &mut dyn ContentHasher, io: &IoPolicy) -> Result<(), HashError> {
+    let mut file = io.open(path)?;
+    let hashed = hash_range_of(&mut file, offset, len, hasher, io);
+    io.finish(&file);
+    hashed
+}
+
+fn hash_range_of(file: &mut File, offset: u64, len: u64, hasher: &mut dyn ContentHasher, io: &IoPolicy) -> Result<(), HashError> {
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut buffer = vec![0u8; 1048576];
+    let mut remaining = len;
+    while remaining > 0 {
+        let want = remaining.min(buffer.len() as u64) as usize;
+        let n = read_up_to(file, &mut buffer[..want])?;
+        if n < want {
+            return Err(HashError::Truncated { expected: offset + len, read: offset + len - remaining + n as u64 });
         }
+        io.consume(n);
+        hasher.update(&buffer[..n]);
+        remaining -= n as u64;
     }
-    
-    if scan_zips {
-        for ext in ["zip", "tar", "gz", "7z", "rar"] {
-            whitelist_exts.insert(ext.to_string());
+    Ok(())
+}
+
+pub fn scan_directory(
+    path: &str, 
+    config: &ScanConfig,
+    cancel: &CancellationToken,
+    visited: &VisitedDirs,
+    io: &IoPolicy,
+) -> DirectoryScan {
+    let scan_hidden = config.scan_hidden;
+    let follow_symlinks = config.follow_symlinks;
+    let find_empty = config.find_empty;
+
+    // Roots inside an excluded location (e.g. under /System) are skipped entirely
+    if config.exclusions.is_excluded(Path::new(path), true) {
+        return DirectoryScan::default();
+    }
+
+    // Another root may already have reached this one through a symlink
+    if follow_symlinks {
+        if let Some(target) = visited.visit(Path::new(path), false) {
+            return DirectoryScan {
+                aliases: vec![SymlinkAlias { path: path.to_string(), target }],
+                ..DirectoryScan::default()
+            };
         }
     }
 
+    // Build Whitelist dynamically (Using HashSet for O(1) lookup)
+    let mut enabled = HashSet::new();
+    if config.scan_images { enabled.insert(FileCategory::Image); }
+    if config.scan_videos { enabled.insert(FileCategory::Video); }
+    if config.scan_zips { enabled.insert(FileCategory::Archive); }
     // Always include documents and audio
-    for ext in ["pdf", "docx", "xlsx", "pptx", "txt", "md", "mp3", "wav", "flac", "m4a", "ogg"] {
-        whitelist_exts.insert(ext.to_string());
-    }
+    enabled.insert(FileCategory::Document);
+    enabled.insert(FileCategory::Audio);
 
+    let detection = config.content_detection;
 
-    
-    jwalk::WalkDirGeneric::<((), ())>::new(path)
+    // Stop descending into new directories once cancelled; the iterator below
+    // then drains whatever jwalk already queued.
+    let walker_cancel = cancel.clone();
+    // Excluded entries are dropped before jwalk reads them, so excluded folders are never walked.
+    let exclusions = config.exclusions.clone();
+    // Symlinked folders that lead somewhere already walked are recorded here instead of descended into
+    let aliases: Arc<Mutex<Vec<SymlinkAlias>>> = Arc::default();
+    let walker_aliases = aliases.clone();
+    let walker_visited = visited.clone();
+    let followed = FollowedLinks::default();
+    let walker_followed = followed.clone();
+    let walker_root = PathBuf::from(path);
+    let errors = ErrorLog::default();
+    let mut contents = DirectoryContents::default();
+    let mut empty_files = Vec::new();
+
+    let files: Vec<FileMetadata> = jwalk::WalkDirGeneric::<((), ())>::new(path)
         .skip_hidden(!scan_hidden)
-        .follow_links(false) // Core protection: never follow symlinks to avoid recursion or duplication
+        // Off by default: following links risks recursion and walking the same data twice.
+        // When on, every folder is keyed by (device, inode) so each one is read only once.
+        .follow_links(follow_symlinks)
         .parallelism(jwalk::Parallelism::RayonNewPool(0))
+        .process_read_dir(move |depth, _, _, children| {
+            if walker_cancel.is_cancelled() {
+                children.clear();
+                return;
+            }
+            children.retain_mut(|child| match child {
+                Ok(entry) => {
+                    if exclusions.is_excluded(&entry.path(), entry.file_type.is_dir()) {
+                        return false;
+                    }
+                    // The root (depth None) was marked visited before the walk
+                    if follow_symlinks && depth.is_some() && entry.read_children_path.is_some() {
+                        // Below a followed link, folders go by their real path too
+                        let path = walker_followed.resolve(&entry.path()).unwrap_or_else(|| entry.path());
+                        if entry.path_is_symlink() {
+                            // Only links are aliases; they point at the real path whether or not it is walked yet
+                            let target = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                            walker_aliases.lock().unwrap().push(SymlinkAlias {
+                                path: path.to_string_lossy().into_owned(),
+                                target: target.to_string_lossy().into_owned(),
+                            });
+                            // A target inside this root is walked under its own name anyway
+                            if target.starts_with(&walker_root) || walker_visited.visit(&target, true).is_some() {
+                                entry.read_children_path = None;
+                                return false;
+                            }
+                            walker_followed.insert(entry.path(), target);
+                        } else if let Some(first) = walker_visited.visit(&path, false) {
+                            entry.read_children_path = None;
+                            // The same real directory under another name, e.g. through a bind mount
+                            if first != path.to_string_lossy() {
+                                walker_aliases.lock().unwrap().push(SymlinkAlias {
+                                    path: path.to_string_lossy().into_owned(),
+                                    target: first,
+                                });
+                            }
+                            return false;
+                        }
+                    }
+                    true
+                }
+                // jwalk's own loop check (link target is a literal ancestor path)
+                Err(err) => match (err.path(), err.loop_ancestor()) {
+                    (Some(link), Some(ancestor)) => {
+                        let link = walker_followed.resolve(link).unwrap_or_else(|| link.to_path_buf());
+                        walker_aliases.lock().unwrap().push(SymlinkAlias {
+                            path: link.to_string_lossy().into_owned(),
+                            target: ancestor.to_string_lossy().into_owned(),
+                        });
+                        false
+                    }
+                    _ => true,
+                },
+            });
+        })
         .into_iter()
+        .take_while(|_| !cancel.is_cancelled())
         .filter_map(|e| {
             match e {
-                Ok(entry) => Some(entry),
+                Ok(entry) => {
+                    // Unreadable folders still show up as entries; their listing error is attached
+                    if let Some(err) = &entry.read_children_error {
+                        errors.push(walk_error(err, &entry.path(), ScanPhase::Traversal));
+                    }
+                    Some(entry)
+                }
                 Err(err) => {
-                    eprintln!("Scan error (permission/access): {}", err);
+                    errors.push(walk_error(&err, Path::new(path), ScanPhase::Traversal));
                     None
                 }
             }
         })
         .filter_map(|entry| {
-            let path_buf = entry.path();
-            let path_str = path_buf.to_string_lossy();
+            let mut path_buf = entry.path();
+            if follow_symlinks {
+                if let Some(real) = followed.resolve(&path_buf) {
+                    path_buf = real;
+                }
+            }
+
+            if find_empty && entry.depth > 0 {
+                if entry.file_type.is_dir() && !entry.path_is_symlink() && entry.read_children_error.is_none() {
+                    contents.directory(path_buf.clone());
+                } else if let Some(parent) = path_buf.parent() {
+                    contents.occupy(parent);
+                }
+            }
             
             // Dot-folder explicit exclusion
             if !scan_hidden {
@@ -146,62 +521,138 @@ pub fn scan_directory(
                 }
             }
 
-            // 1. Absolute Path Blacklist Check
-            if blacklist.iter().any(|b| path_str.starts_with(b)) {
-                return None;
-            }
-
-            // 2. Folder Name Blacklist Check (for Dev tools/Libraries)
-            if entry.file_type.is_dir() {
-                if let Some(name) = path_buf.file_name() {
-                    let name_str = name.to_string_lossy();
-                    if dev_black_names.iter().any(|&b| name_str == b) {
-                        return None; 
-                    }
-                }
-            }
-
             if entry.file_type.is_file() {
-                // Optimization: Use a simpler check for parent folders
-                let mut components = path_buf.components();
-                while let Some(comp) = components.next() {
-                    if let Some(name) = comp.as_os_str().to_str() {
-                        if dev_black_names.contains(&name) {
-                            return None;
-                        }
-                    }
+                // Empty files of any type are reported on their own; they can't be duplicates worth keeping
+                if find_empty && !entry.path_is_symlink() && entry.metadata().is_ok_and(|m| m.len() == 0) {
+                    empty_files.push(path_buf.to_string_lossy().into_owned());
+                    return None;
                 }
 
-                // 3. Extension Whitelist Check (O(1))
-                let ext = path_buf.extension()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-                
-                if !whitelist_exts.contains(&ext) {
+                // Extension Whitelist Check (O(1)). Other modes decide after sniffing.
+                let ext_category = content::category_for_path(&path_buf);
+                if detection == ContentDetection::Extension
+                    && !ext_category.is_some_and(|c| enabled.contains(&c)) {
                     return None;
                 }
 
-                if let Ok(metadata) = entry.metadata() {
-                    // Min File Size Filter
-                    if metadata.len() < min_file_size {
+                let metadata = match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(err) => {
+                        errors.push(walk_error(&err, &path_buf, ScanPhase::Metadata));
                         return None;
                     }
+                };
 
-                    return Some(FileMetadata {
-                        path: path_str.into_owned(),
-                        size: metadata.len(),
-                        modified: metadata.modified()
-                            .unwrap_or(SystemTime::UNIX_EPOCH)
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                        partial_hash: None,
-                        full_hash: None,
+                // Min File Size Filter
+                if metadata.len() < config.min_file_size {
+                    return None;
+                }
+
+                // Report a symlinked file under its real path; deleting the link would free nothing
+                if entry.path_is_symlink() {
+                    let target = match std::fs::canonicalize(&path_buf) {
+                        Ok(target) => target,
+                        Err(err) => {
+                            errors.push(ScanError::from_io(&path_buf, ScanPhase::Metadata, &err));
+                            return None;
+                        }
+                    };
+                    aliases.lock().unwrap().push(SymlinkAlias {
+                        path: path_buf.to_string_lossy().into_owned(),
+                        target: target.to_string_lossy().into_owned(),
                     });
+                    path_buf = target;
                 }
+
+                return Some(FileMetadata::new(
+                    path_buf.to_string_lossy().into_owned(),
+                    &metadata,
+                    ext_category.unwrap_or(FileCategory::Other),
+                ));
             }
             None
         })
-        .collect()
+        .collect();
+
+    let aliases = std::mem::take(&mut *aliases.lock().unwrap());
+    let errors = errors.into_sorted();
+    let empty_dirs = if find_empty { contents.empty_dirs(&config.exclusions) } else { Vec::new() };
+    if detection == ContentDetection::Extension {
+        return DirectoryScan { files, aliases, errors, empty_files, empty_dirs };
+    }
+
+    // Sniff in parallel after traversal; only files that passed the size filter are read.
+    let files = files.into_par_iter()
+        .filter_map(|mut f| {
+            if let Some((mime, category)) = content::sniff(Path::new(&f.path), io) {
+                f.mime_type = Some(mime);
+                f.category = category;
+            }
+            match detection {
+                ContentDetection::All => Some(f),
+                _ if enabled.contains(&f.category) => Some(f),
+                _ => None,
+            }
+        })
+        .collect();
+    DirectoryScan { files, aliases, errors, empty_files, empty_dirs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_root, write};
+
+    /// Hands out at most three bytes per call and is interrupted once along the way.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        interrupted: bool,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if !self.interrupted {
+                self.interrupted = true;
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(self.data.len()).min(3);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn read_up_to_fills_the_buffer_across_short_reads() {
+        let data = b"hello, world";
+        let mut buf = [0u8; 8];
+        let n = read_up_to(&mut Trickle { data, interrupted: false }, &mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello, w");
+
+        // Only the end of the file stops it early
+        let mut buf = [0u8; 32];
+        let n = read_up_to(&mut Trickle { data, interrupted: false }, &mut buf).unwrap();
+        assert_eq!(&buf[..n], data);
+    }
+
+    #[test]
+    fn short_reads_hash_like_whole_reads() {
+        let io = IoPolicy::default();
+        let data = b"hello, world";
+        let trickled = stream_hash(Trickle { data, interrupted: false }, data.len() as u64, HashAlgorithm::Blake3, &io).unwrap();
+        assert_eq!(trickled, stream_hash(&data[..], data.len() as u64, HashAlgorithm::Blake3, &io).unwrap());
+    }
+
+    #[test]
+    fn file_shorter_than_its_size_is_truncated() {
+        let io = IoPolicy::default();
+        let result = stream_hash(&b"abc"[..], 5, HashAlgorithm::Blake3, &io);
+        assert!(matches!(result, Err(HashError::Truncated { expected: 5, read: 3 })), "{:?}", result.err());
+
+        let (_dir, root) = temp_root();
+        let path = write(&root, "a.pdf", b"0123456789");
+        let mut hasher = HashAlgorithm::Blake3.hasher();
+        let result = hash_range(&path, 4, 10, hasher.as_mut(), &io);
+        assert!(matches!(result, Err(HashError::Truncated { expected: 14, read: 10 })), "{:?}", result.err());
+    }
 }
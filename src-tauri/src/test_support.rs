@@ -0,0 +1,34 @@
+//! Temp-dir fixtures shared by the unit tests.
+
+use crate::content::FileCategory;
+use crate::scanner::FileMetadata;
+use std::path::{Path, PathBuf};
+
+/// A fresh temp dir and its canonical path, which is what scans report. The prefix keeps
+/// the directory from counting as hidden.
+pub fn temp_root() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::Builder::new().prefix("dedupe-test").tempdir().unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    (dir, root)
+}
+
+pub fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Writes `contents` to `dir/name` and returns the path.
+pub fn write(dir: &Path, name: &str, contents: &[u8]) -> String {
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path_string(&path)
+}
+
+/// The file at `path` as traversal would find it.
+pub fn metadata(path: &str) -> FileMetadata {
+    FileMetadata::new(path.to_string(), &std::fs::metadata(path).unwrap(), FileCategory::Document)
+}
+
+/// `len` bytes that differ from position to position, varied by `seed`.
+pub fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8 ^ seed).collect()
+}
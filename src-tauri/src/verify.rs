@@ -0,0 +1,212 @@
+use crate::errors::{ErrorLog, ScanError, ScanPhase};
+use crate::io_policy::IoPolicy;
+use crate::scanner::{read_up_to, FileMetadata};
+use serde::Serialize;
+use std::fs::File;
+use std::path::Path;
+
+/// Bytes compared per file per round.
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Files a group holds open at once, reference included. Groups are verified in
+/// parallel, so this bounds both descriptors and buffers however large a group is.
+const MAX_OPEN_FILES: usize = 8;
+
+/// How the files in a duplicate group were confirmed to be identical.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verification {
+    /// Same size and same full-content hash.
+    #[default]
+    FullHash,
+    /// Additionally compared byte by byte.
+    ByteForByte,
+}
+
+struct Candidate {
+    file: FileMetadata,
+    reader: File,
+}
+
+/// Compares files that share a hash and returns the subsets whose contents are truly
+/// identical. Files that can't be read are logged and left out; subsets of one file are dropped.
+///
+/// The first remaining file becomes the reference and the others are streamed against
+/// it in batches of at most `MAX_OPEN_FILES`, so a mismatch stops reading that file as
+/// soon as it is found. Files that differ from the reference are compared among
+/// themselves in the next round. Every read goes through `io`, so verification honours
+/// background mode and the read cap like the hashing passes.
+pub fn split_identical(files: Vec<FileMetadata>, io: &IoPolicy, errors: &ErrorLog) -> Vec<Vec<FileMetadata>> {
+    let mut remaining = files;
+    let mut identical = Vec::new();
+
+    while remaining.len() > 1 {
+        let reference = remaining.remove(0);
+        let mut class = vec![reference];
+        let mut different = Vec::new();
+        let mut pending = std::mem::take(&mut remaining).into_iter();
+
+        loop {
+            let batch: Vec<FileMetadata> = pending.by_ref().take(MAX_OPEN_FILES - 1).collect();
+            if batch.is_empty() { break; }
+            match compare_batch(&class[0], batch, io, errors) {
+                Ok((same, differ)) => {
+                    class.extend(same);
+                    different.extend(differ);
+                }
+                Err(unchecked) => {
+                    // The reference itself became unreadable; the rest get a new one
+                    different.extend(unchecked);
+                    different.extend(class.drain(1..));
+                    different.extend(pending.by_ref());
+                    class.clear();
+                    break;
+                }
+            }
+        }
+
+        if class.len() > 1 {
+            identical.push(class);
+        }
+        remaining = different;
+    }
+    identical
+}
+
+/// Streams `batch` against `reference` in lockstep. Returns the members with identical
+/// contents and those that differ. If the reference can't be read, the members are
+/// handed back unchecked. Members that can't be read are logged and dropped.
+fn compare_batch(reference: &FileMetadata, batch: Vec<FileMetadata>, io: &IoPolicy, errors: &ErrorLog) -> Result<(Vec<FileMetadata>, Vec<FileMetadata>), Vec<FileMetadata>> {
+    let log = |file: &FileMetadata, e: &std::io::Error| {
+        errors.push(ScanError::from_io(Path::new(&file.path), ScanPhase::Verification, e));
+    };
+
+    let mut reference_reader = match io.open(&reference.path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            log(reference, &e);
+            return Err(batch);
+        }
+    };
+    let mut members: Vec<Candidate> = batch.into_iter()
+        .filter_map(|file| match io.open(&file.path) {
+            Ok(reader) => Some(Candidate { file, reader }),
+            Err(e) => {
+                log(&file, &e);
+                None
+            }
+        })
+        .collect();
+
+    let mut expected = vec![0; CHUNK_SIZE];
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let mut different = Vec::new();
+    let release = |candidate: Candidate| {
+        io.finish(&candidate.reader);
+        candidate.file
+    };
+    let outcome = loop {
+        if members.is_empty() {
+            break Ok((Vec::new(), different));
+        }
+        let n = match read_up_to(&mut reference_reader, &mut expected) {
+            Ok(n) => n,
+            Err(e) => {
+                log(reference, &e);
+                different.extend(members.into_iter().map(release));
+                break Err(different);
+            }
+        };
+        io.consume(n);
+
+        let mut still_equal = Vec::with_capacity(members.len());
+        for mut candidate in members {
+            match read_up_to(&mut candidate.reader, &mut buffer) {
+                Ok(read) => {
+                    io.consume(read);
+                    if read == n && buffer[..n] == expected[..n] {
+                        still_equal.push(candidate);
+                    } else {
+                        different.push(release(candidate));
+                    }
+                }
+                Err(e) => {
+                    log(&candidate.file, &e);
+                    release(candidate);
+                }
+            }
+        }
+        members = still_equal;
+
+        if n == 0 {
+            // The reference and every remaining member reached end of file together
+            break Ok((members.into_iter().map(release).collect(), different));
+        }
+    };
+    io.finish(&reference_reader);
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{metadata, temp_root, write};
+
+    fn names(class: &[FileMetadata]) -> Vec<String> {
+        let mut names: Vec<String> = class.iter()
+            .map(|f| Path::new(&f.path).file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn splits_group_larger_than_open_file_limit() {
+        let (_dir, root) = temp_root();
+        // Several chunks long, so mismatches in the last chunk are only seen late
+        let contents: Vec<u8> = (0..CHUNK_SIZE * 2 + 100).map(|i| (i % 251) as u8).collect();
+        let mut last_byte_differs = contents.clone();
+        *last_byte_differs.last_mut().unwrap() ^= 1;
+
+        let mut files = Vec::new();
+        let mut expected_same = Vec::new();
+        for i in 0..MAX_OPEN_FILES * 2 + 1 {
+            let name = format!("same-{:02}", i);
+            files.push(metadata(&write(&root, &name, &contents)));
+            expected_same.push(name);
+            // Interleave the odd ones so they land in different batches
+            if i == 3 || i == 12 {
+                files.push(metadata(&write(&root, &format!("tail-{:02}", i), &last_byte_differs)));
+            }
+        }
+        files.push(metadata(&write(&root, "lonely", &[0; CHUNK_SIZE * 2 + 100])));
+
+        let errors = ErrorLog::default();
+        let classes = split_identical(files, &IoPolicy::default(), &errors);
+        assert!(errors.into_sorted().is_empty());
+        let mut classes: Vec<Vec<String>> = classes.iter().map(|class| names(class)).collect();
+        classes.sort();
+        assert_eq!(classes, [expected_same, vec!["tail-03".to_string(), "tail-12".to_string()]]);
+    }
+
+    #[test]
+    fn logs_unreadable_files_and_keeps_the_rest() {
+        let (_dir, root) = temp_root();
+        // The unreadable file comes first, so it would have been the reference
+        let missing = metadata(&write(&root, "missing", b"same"));
+        std::fs::remove_file(&missing.path).unwrap();
+        let files = vec![
+            missing,
+            metadata(&write(&root, "a", b"same")),
+            metadata(&write(&root, "b", b"same")),
+            metadata(&write(&root, "c", b"diff")),
+        ];
+
+        let errors = ErrorLog::default();
+        let classes = split_identical(files, &IoPolicy::default(), &errors);
+        assert_eq!(classes.iter().map(|class| names(class)).collect::<Vec<_>>(), [["a", "b"]]);
+        let errors = errors.into_sorted();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].path.ends_with("missing"));
+    }
+}
@@ -2,7 +2,7 @@ import { SidebarProvider, SidebarInset } from "@/components/ui/sidebar";
 import { AppSidebar } from "./components/Sidebar";
 import { ResultsView } from "./components/ResultsView";
 import { ScanQueueView } from "./components/ScanQueueView";
-import { useStore, ScanResult } from "./store/useStore";
+import { useStore, ScanResult, ScanJobSummary } from "./store/useStore";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { OnboardingWizard } from "./components/OnboardingWizard";
@@ -16,6 +16,7 @@ import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
 import { useEffect } from "react";
 
 import { Toaster } from "@/components/ui/sonner";
+import { toast } from "sonner";
 
 function App() {
   const { isScanning, scanQueue, scanResults, setResults, isOnboarded, setScanProgress, activeView, setActiveView } = useStore();
@@ -27,10 +28,13 @@ function App() {
     }
   }, [scanResults, isScanning, setActiveView]);
 
-  // Listen for scan progress
+  // Listen for scan progress of the job we are tracking
   useEffect(() => {
-    const unlistenPromise = listen<{ current: number; total: number; file: string; }>("scan-progress", (event) => {
-      setScanProgress(event.payload);
+    const unlistenPromise = listen<{ job_id: string; current: number; total: number; file: string; }>("scan-progress", (event) => {
+      const { job_id, ...progress } = event.payload;
+      if (job_id === useStore.getState().activeJobId) {
+        setScanProgress(progress);
+      }
     });
 
     return () => {
@@ -38,10 +42,97 @@ function App() {
     };
   }, [setScanProgress]);
 
+  // Scans run as backend jobs, so reattach to one that is still running after a reload
+  useEffect(() => {
+    invoke<ScanJobSummary[]>("list_scan_jobs").then((jobs) => {
+      const running = jobs.find(job => job.status === "running");
+      if (running && !useStore.getState().isScanning) {
+        const { setScanning, setScanPhase } = useStore.getState();
+        setScanning(true);
+        setScanPhase('partial');
+        trackJob(running.id);
+      }
+    }).catch((error) => console.error("Failed to list scan jobs:", error));
+  }, []);
+
+  // Without a cache every scan rehashes everything, so say why it is missing
+  useEffect(() => {
+    invoke<string | null>("get_cache_error").then((error) => {
+      if (error) {
+        toast.error("Hash cache unavailable; scans will rehash every file", { description: error });
+      }
+    }).catch((error) => console.error("Failed to get cache status:", error));
+  }, []);
+
+  // Polls a job until it finishes, then loads its result into the store
+  const trackJob = async (jobId: string) => {
+    const { setScanPhase, setScanning, setResults, setScanTimestamp, setScanProgress, setActiveJobId } = useStore.getState();
+    setActiveJobId(jobId);
+
+    try {
+      let status = await invoke<ScanJobSummary>("get_scan_status", { jobId });
+      while (status.status === "running") {
+        await new Promise(r => setTimeout(r, 500));
+        status = await invoke<ScanJobSummary>("get_scan_status", { jobId });
+      }
+
+      if (typeof status.status === "object") {
+        toast.error("Scan failed", { description: status.status.failed.message });
+        return;
+      }
+
+      const response = await invoke<ScanResult | null>("get_scan_result", { jobId });
+      if (!response) return;
+
+      setScanPhase('full');
+      await new Promise(r => setTimeout(r, 400));
+
+      if (response.merged_roots.length > 0) {
+        toast.info(`${response.merged_roots.length} overlapping folder(s) were scanned once`, {
+          description: response.merged_roots.map(r => `${r.path} → ${r.merged_into}`).join('\n'),
+        });
+      }
+
+      if (response.errors.length > 0) {
+        const skippedFolders = response.errors.filter(e => e.phase === 'traversal').length;
+        toast.warning(`${response.errors.length} path(s) could not be read`, {
+          description: [
+            skippedFolders > 0 ? `${skippedFolders} folder(s) were skipped. Grant access and re-run to include them.` : null,
+            ...response.errors.slice(0, 5).map(e => `${e.kind.replace('_', ' ')}: ${e.path}`),
+            response.errors.length > 5 ? `…and ${response.errors.length - 5} more` : null,
+          ].filter(Boolean).join('\n'),
+        });
+      }
+
+      if (response.unstable_files.length > 0) {
+        toast.warning(`${response.unstable_files.length} file(s) changed during the scan and were skipped`, {
+          description: [
+            ...response.unstable_files.slice(0, 5).map(f => f.current_size === null ? `removed: ${f.path}` : f.path),
+            response.unstable_files.length > 5 ? `…and ${response.unstable_files.length - 5} more` : null,
+          ].filter(Boolean).join('\n'),
+        });
+      }
+
+      if (response.cancelled) {
+        toast.info(`Scan cancelled. Showing ${response.groups.length} confirmed groups.`);
+      }
+
+      setResults(response);
+      setScanTimestamp(Date.now()); // Force UI refresh
+    } catch (error) {
+      console.error("Scan failed:", error);
+    } finally {
+      setScanning(false);
+      setScanPhase('idle');
+      setScanProgress(null);
+      setActiveJobId(null);
+    }
+  };
+
 
   const handleStartScan = async () => {
     if (scanQueue.length === 0) return;
-    const { setScanPhase, setScanning, setResults, scanHidden, scanImages, scanVideos, scanZips, minFileSize, setScanTimestamp, setScanProgress } = useStore.getState();
+    const { setScanPhase, setScanning, setResults, scanHidden, scanImages, scanVideos, scanZips, minFileSize, contentDetection, followSymlinks, hashAlgorithm, verifyContents, progressiveHashing, readOrder, backgroundIo, maxReadMbps, findEmpty, cacheLimit, setScanProgress } = useStore.getState();
 
     setResults(null);
     setScanning(true);
@@ -53,29 +144,46 @@ function App() {
       await new Promise(r => setTimeout(r, 600));
       setScanPhase('partial');
 
-      const response = await invoke<ScanResult>("start_scan", {
+      const jobId = await invoke<string>("start_scan", {
         paths: scanQueue,
-        scanHidden,
-        scanImages,
-        scanVideos,
-        scanZips,
-        minFileSize
+        options: {
+          scanHidden,
+          scanImages,
+          scanVideos,
+          scanZips,
+          minFileSize,
+          contentDetection,
+          followSymlinks,
+          hashAlgorithm,
+          verifyContents,
+          progressiveHashing,
+          readOrder,
+          backgroundIo,
+          maxReadMbps,
+          findEmpty,
+          cacheLimit
+        }
       });
 
-      setScanPhase('full');
-      await new Promise(r => setTimeout(r, 400));
-
-      setResults(response);
-      setScanTimestamp(Date.now()); // Force UI refresh
+      await trackJob(jobId);
     } catch (error) {
       console.error("Scan failed:", error);
-    } finally {
       setScanning(false);
       setScanPhase('idle');
       setScanProgress(null);
     }
   };
 
+  const handleCancelScan = async () => {
+    const { activeJobId } = useStore.getState();
+    if (!activeJobId) return;
+    try {
+      await invoke<boolean>("cancel_scan", { jobId: activeJobId });
+    } catch (error) {
+      console.error("Cancel failed:", error);
+    }
+  };
+
   const clearResults = () => {
     setResults(null);
     setActiveView("queue");
@@ -190,6 +298,9 @@ function App() {
                       <p className="text-muted-foreground text-sm font-medium">Analyzing file trees across {scanQueue.length} virtual targets...</p>
                     )}
                   </div>
+                  <Button variant="outline" size="sm" onClick={handleCancelScan} className="rounded-full px-6 font-bold text-xs">
+                    Cancel Scan
+                  </Button>
                 </div>
               </div>
             )}
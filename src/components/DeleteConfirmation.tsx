@@ -1,5 +1,6 @@
 import { useStore } from "../store/useStore";
 import { formatSize } from "../lib/utils";
+import { categoryNames } from "../lib/dataTransform";
 import {
     Trash2,
     Loader2,
@@ -7,6 +8,7 @@ import {
 } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
 import { useState, useMemo } from "react";
+import { toast } from "sonner";
 import {
     AlertDialog,
     AlertDialogContent,
@@ -20,7 +22,7 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
     const { selectionQueue, scanResults, removeDeletedFromResults } = useStore();
     const [isDeleting, setIsDeleting] = useState(false);
 
-    const { totalSize, categories, previewFiles } = useMemo(() => {
+    const { totalSize, categories, previewFiles, linkPaths } = useMemo(() => {
         let size = 0;
         const cats = {
             Images: 0,
@@ -30,20 +32,31 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
             Others: 0
         };
         const allFiles: any[] = [];
+        // Other names of selected files; the data only goes away once every name is deleted
+        const links: string[] = [];
 
         if (scanResults) {
+            const emptyPaths = new Set([...scanResults.empty_files, ...scanResults.empty_dirs]);
             selectionQueue.forEach(path => {
+                // Empty files and folders free no space but are removed the same way
+                if (emptyPaths.has(path)) {
+                    allFiles.push({ path, size: 0 });
+                    cats.Others++;
+                    return;
+                }
                 for (const group of scanResults.groups) {
-                    const file = group.find(f => f.path === path);
+                    const file = group.files.find(f => f.path === path);
                     if (file) {
-                        size += file.size;
+                        // A copy still linked from outside the scanned folders stays on disk
+                        if (file.nlink <= 1 + file.hard_links.length) {
+                            size += file.size;
+                        }
                         allFiles.push(file);
-                        const ext = file.path.split('.').pop()?.toLowerCase() || '';
-                        if (['jpg', 'jpeg', 'png', 'gif', 'webp', 'heic'].includes(ext)) cats.Images++;
-                        else if (['mp4', 'mov', 'avi', 'mkv', 'webm'].includes(ext)) cats.Videos++;
-                        else if (['pdf', 'docx', 'xlsx', 'txt', 'md'].includes(ext)) cats.Documents++;
-                        else if (['zip', 'rar', '7z', 'tar', 'gz'].includes(ext)) cats.Archives++;
-                        else cats.Others++;
+                        file.hard_links.forEach(link => {
+                            links.push(link);
+                            allFiles.push({ path: link, size: file.size, linkOf: file.path });
+                        });
+                        cats[categoryNames[file.category] ?? 'Others']++;
                         break;
                     }
                 }
@@ -53,17 +66,43 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
         return {
             totalSize: size,
             categories: Object.entries(cats).filter(([_, count]) => count > 0),
-            previewFiles: allFiles.slice(0, 100)
+            previewFiles: allFiles.slice(0, 100),
+            linkPaths: links,
         };
     }, [selectionQueue, scanResults]);
 
+    const totalCount = selectionQueue.length + linkPaths.length;
+
     const handleDelete = async () => {
         setIsDeleting(true);
         try {
-            const report = await invoke<{ success_count: number; fail_count: number }>("delete_selections", {
-                paths: selectionQueue,
-            });
-            console.log("Deletion Report:", report);
+            // Empty items go through a delete that refuses anything that gained content since the scan
+            const emptyFiles = new Set(scanResults?.empty_files ?? []);
+            const emptyDirs = new Set(scanResults?.empty_dirs ?? []);
+            const files = selectionQueue.filter(path => emptyFiles.has(path));
+            const dirs = selectionQueue.filter(path => emptyDirs.has(path));
+            const paths = [
+                ...selectionQueue.filter(path => !emptyFiles.has(path) && !emptyDirs.has(path)),
+                ...linkPaths,
+            ];
+
+            if (paths.length > 0) {
+                const report = await invoke<{ success_count: number; fail_count: number }>("delete_selections", {
+                    paths,
+                });
+                console.log("Deletion Report:", report);
+            }
+            if (files.length > 0 || dirs.length > 0) {
+                const report = await invoke<{ success_count: number; fail_count: number; errors: string[] }>("delete_empty_items", {
+                    files,
+                    dirs,
+                });
+                if (report.fail_count > 0) {
+                    toast.warning(`${report.fail_count} empty item(s) were not deleted`, {
+                        description: report.errors.slice(0, 5).join('\n'),
+                    });
+                }
+            }
 
             // Remove successfully deleted files from UI
             removeDeletedFromResults(selectionQueue);
@@ -83,7 +122,7 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
                     <div className="p-4 border-b border-white/5 bg-white/[0.02]">
                         <span className="text-[10px] font-black uppercase tracking-widest text-muted-foreground">Target Selection</span>
                         <div className="mt-1 text-[9px] font-mono opacity-50 text-destructive">
-                            {selectionQueue.length} objects marked for removal
+                            {totalCount} objects marked for removal
                         </div>
                     </div>
                     <div className="flex-1 overflow-y-auto p-2 space-y-1 scrollbar-none">
@@ -94,7 +133,7 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
                                         <div className="w-1.5 h-1.5 rounded-full bg-destructive/50 group-hover:bg-destructive transition-colors shrink-0" />
                                         <span className="text-[10px] font-bold text-foreground/90 truncate">{file.path.split('/').pop()}</span>
                                     </div>
-                                    <span className="text-[9px] font-mono text-muted-foreground/60">{formatSize(file.size)}</span>
+                                    <span className="text-[9px] font-mono text-muted-foreground/60">{file.linkOf ? 'hard link' : formatSize(file.size)}</span>
                                 </div>
                                 <div className="text-[8px] font-mono text-muted-foreground truncate pl-3.5 opacity-40">
                                     {file.path}
@@ -106,10 +145,10 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
                                 <span className="text-[10px] text-muted-foreground uppercase tracking-widest">Resolving Metadata...</span>
                             </div>
                         )}
-                        {selectionQueue.length > previewFiles.length && (
+                        {totalCount > previewFiles.length && (
                             <div className="px-3 py-2 text-center">
                                 <span className="text-[9px] italic text-muted-foreground opacity-50">
-                                    + {selectionQueue.length - previewFiles.length} more items...
+                                    + {totalCount - previewFiles.length} more items...
                                 </span>
                             </div>
                         )}
@@ -144,7 +183,7 @@ export function DeleteConfirmation({ isOpen, onClose }: { isOpen: boolean; onClo
                             </div>
 
                             <AlertDialogDescription className="text-xs font-medium text-muted-foreground leading-relaxed">
-                                You are about to permanently remove <span className="text-white font-bold">{selectionQueue.length} files</span>. This action interacts directly with your file system.
+                                You are about to permanently remove <span className="text-white font-bold">{totalCount} files</span>{linkPaths.length > 0 && <> (including {linkPaths.length} hard link{linkPaths.length > 1 ? 's' : ''} to the selected files)</>}. This action interacts directly with your file system.
                             </AlertDialogDescription>
 
                             {/* Impact Chips - Compact Grid */}
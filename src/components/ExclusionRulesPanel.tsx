@@ -0,0 +1,147 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Ban, Plus, RotateCcw, X } from "lucide-react";
+import { toast } from "sonner";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+
+export type ExclusionRuleKind = 'name' | 'folder' | 'path' | 'glob' | 'regex';
+
+export type ExclusionRule =
+    | { kind: ExclusionRuleKind; value: string }
+    // Hides entries in the explorer only; scans still include them
+    | { kind: 'explorer_only'; value: ExclusionRule };
+
+// What a rule list row shows: explorer-only rules are labelled by their own kind
+const describeRule = (rule: ExclusionRule): { label: string; value: string } => {
+    if (rule.kind === 'explorer_only') {
+        return { label: `${describeRule(rule.value).label}*`, value: describeRule(rule.value).value };
+    }
+    return { label: rule.kind, value: rule.value };
+};
+
+const kindTitles: Record<ExclusionRuleKind, string> = {
+    name: "File or folder name, any case",
+    folder: "Folder name, exact case; files with this name are still scanned",
+    path: "This path and everything below it",
+    glob: "Glob on the name, or on the full path if it contains /",
+    regex: "Regular expression on the full path",
+};
+
+const placeholders: Record<ExclusionRuleKind, string> = {
+    name: "node_modules",
+    folder: "build",
+    path: "/Volumes/Backup/Old",
+    glob: "*.photoslibrary",
+    regex: "/cache/[^/]+$",
+};
+
+export function ExclusionRulesPanel({ disabled }: { disabled?: boolean }) {
+    const [rules, setRules] = useState<ExclusionRule[]>([]);
+    const [kind, setKind] = useState<ExclusionRuleKind>('name');
+    const [value, setValue] = useState("");
+
+    useEffect(() => {
+        invoke<ExclusionRule[]>("get_exclusion_rules")
+            .then(setRules)
+            .catch((error) => console.error("Failed to load exclusion rules", error));
+    }, []);
+
+    const saveRules = async (next: ExclusionRule[]) => {
+        try {
+            await invoke("set_exclusion_rules", { rules: next });
+            setRules(next);
+            return true;
+        } catch (error) {
+            toast.error("Invalid Rule", { description: String(error) });
+            return false;
+        }
+    };
+
+    const addRule = async () => {
+        const trimmed = value.trim();
+        if (!trimmed) return;
+        if (await saveRules([...rules, { kind, value: trimmed }])) {
+            setValue("");
+        }
+    };
+
+    const resetRules = async () => {
+        try {
+            setRules(await invoke<ExclusionRule[]>("reset_exclusion_rules"));
+        } catch (error) {
+            toast.error("Reset Failed", { description: String(error) });
+        }
+    };
+
+    return (
+        <div className="px-3 py-2 space-y-2 bg-muted/20 rounded-xl border border-white/5">
+            <div className="flex items-center justify-between">
+                <div className="flex items-center gap-2">
+                    <Ban className="w-3.5 h-3.5 text-muted-foreground opacity-60" />
+                    <span className="text-[10px] font-black uppercase tracking-widest opacity-60">Exclusions</span>
+                </div>
+                <Button
+                    variant="ghost"
+                    size="icon"
+                    onClick={resetRules}
+                    disabled={disabled}
+                    title="Restore default rules"
+                    className="h-6 w-6"
+                >
+                    <RotateCcw className="w-3 h-3" />
+                </Button>
+            </div>
+
+            <div className="max-h-32 overflow-y-auto space-y-1 scrollbar-none">
+                {rules.map((rule, index) => {
+                    const { label, value } = describeRule(rule);
+                    return (
+                    <div key={`${label}-${value}-${index}`} className="flex items-center gap-2 group">
+                        <span
+                            className="text-[8px] font-black uppercase tracking-wider text-primary/70 w-9 shrink-0"
+                            title={rule.kind === 'explorer_only' ? "Hidden in the explorer only; scans still include it" : kindTitles[rule.kind]}
+                        >{label}</span>
+                        <span className="text-[10px] font-mono truncate flex-1 opacity-80" title={value}>{value}</span>
+                        <button
+                            onClick={() => saveRules(rules.filter((_, i) => i !== index))}
+                            disabled={disabled}
+                            className="opacity-0 group-hover:opacity-60 hover:!opacity-100 transition-opacity"
+                            title="Remove rule"
+                        >
+                            <X className="w-3 h-3" />
+                        </button>
+                    </div>
+                    );
+                })}
+            </div>
+
+            <div className="flex items-center gap-1">
+                <select
+                    value={kind}
+                    onChange={(e) => setKind(e.target.value as ExclusionRuleKind)}
+                    disabled={disabled}
+                    className="h-7 rounded-md bg-transparent border border-input text-[10px] font-bold uppercase px-1"
+                >
+                    <option value="name">Name</option>
+                    <option value="folder">Folder</option>
+                    <option value="path">Path</option>
+                    <option value="glob">Glob</option>
+                    <option value="regex">Regex</option>
+                </select>
+                <Input
+                    value={value}
+                    onChange={(e) => setValue(e.target.value)}
+                    onKeyDown={(e) => e.key === "Enter" && addRule()}
+                    placeholder={placeholders[kind]}
+                    title={kindTitles[kind]}
+                    disabled={disabled}
+                    className="h-7 text-[10px] md:text-[10px] font-mono px-2"
+                />
+                <Button variant="ghost" size="icon" onClick={addRule} disabled={disabled || !value.trim()} className="h-7 w-7 shrink-0">
+                    <Plus className="w-3.5 h-3.5" />
+                </Button>
+            </div>
+        </div>
+    );
+}
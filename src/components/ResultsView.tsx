@@ -17,6 +17,7 @@ import {
     LayoutGrid,
     Binary,
     Folders,
+    FileX,
     Loader2
 } from "lucide-react";
 import { DeleteConfirmation } from "./DeleteConfirmation";
@@ -27,6 +28,7 @@ import { invoke, convertFileSrc } from "@tauri-apps/api/core";
 import { ClusterResultsView } from "./views/ClusterResultsView";
 import { FolderResultsView } from "./views/FolderResultsView";
 import { CategoryResultsView } from "./views/CategoryResultsView";
+import { EmptyResultsView } from "./views/EmptyResultsView";
 import { transformToCategories, transformToFolders } from "../lib/dataTransform";
 
 interface ResultsViewProps {
@@ -38,11 +40,11 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
     const [isConfirmOpen, setConfirmOpen] = useState(false);
     const [previewFile, setPreviewFile] = useState<FileMetadata | null>(null);
     const [previewError, setPreviewError] = useState(false);
-    const [viewMode, setViewMode] = useState<'cluster' | 'folder' | 'category'>('cluster');
+    const [viewMode, setViewMode] = useState<'cluster' | 'folder' | 'category' | 'empty'>('cluster');
     const [isSwitching, setIsSwitching] = useState(false);
     const [searchQuery, setSearchQuery] = useState("");
 
-    const handleViewChange = (mode: 'cluster' | 'folder' | 'category') => {
+    const handleViewChange = (mode: 'cluster' | 'folder' | 'category' | 'empty') => {
         if (mode === viewMode) return;
         setIsSwitching(true);
         setViewMode(mode);
@@ -55,10 +57,16 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
 
         const query = searchQuery.toLowerCase();
         const filtered = scanResults.groups.filter(group =>
-            group.some(file => file.path.toLowerCase().includes(query))
+            group.files.some(file => file.path.toLowerCase().includes(query))
         );
-
-        return { groups: filtered };
+        const matches = (path: string) => path.toLowerCase().includes(query);
+
+        return {
+            ...scanResults,
+            groups: filtered,
+            empty_files: scanResults.empty_files.filter(matches),
+            empty_dirs: scanResults.empty_dirs.filter(matches),
+        };
     }, [scanResults, searchQuery]);
 
     const categoryData = React.useMemo(() => {
@@ -71,7 +79,9 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
         return transformToFolders(filteredResults.groups);
     }, [filteredResults]);
 
-    if (!scanResults || scanResults.groups.length === 0) {
+    const emptyCount = scanResults ? scanResults.empty_files.length + scanResults.empty_dirs.length : 0;
+
+    if (!scanResults || (scanResults.groups.length === 0 && emptyCount === 0)) {
         return (
             <div className="flex-1 flex flex-col items-center justify-center p-12 text-center animate-in fade-in zoom-in-95 duration-500 bg-[#0c0c0c]">
                 <div className="w-24 h-24 bg-white/[0.02] rounded-3xl flex items-center justify-center mb-6 border border-white/5 shadow-2xl relative overflow-hidden group">
@@ -100,18 +110,14 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
 
     const totalReclaimable = React.useMemo(() => {
         if (!scanResults) return 0;
-        return scanResults.groups.reduce((acc, group) => {
-            const groupSize = group[0]?.size || 0;
-            const dupesCount = Math.max(0, group.length - 1);
-            return acc + (groupSize * dupesCount);
-        }, 0);
+        return scanResults.groups.reduce((acc, group) => acc + group.reclaimable_bytes, 0);
     }, [scanResults]);
 
     const filePathMap = React.useMemo(() => {
         const map = new Map<string, number>();
         if (!scanResults) return map;
         scanResults.groups.forEach(group => {
-            group.forEach(file => {
+            group.files.forEach(file => {
                 map.set(file.path, file.size);
             });
         });
@@ -160,7 +166,7 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
     useEffect(() => {
         if (!previewFile || !scanResults) return;
         const exists = scanResults.groups.some(group =>
-            group.some(file => file.path === previewFile.path)
+            group.files.some(file => file.path === previewFile.path)
         );
         if (!exists) setPreviewFile(null);
     }, [scanResults, previewFile]);
@@ -206,7 +212,8 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
                                 {[
                                     { id: 'cluster', icon: LayoutGrid, label: 'Cluster' },
                                     { id: 'category', icon: Binary, label: 'Category' },
-                                    { id: 'folder', icon: Folders, label: 'Folder' }
+                                    { id: 'folder', icon: Folders, label: 'Folder' },
+                                    ...(emptyCount > 0 ? [{ id: 'empty', icon: FileX, label: `Empty (${emptyCount})` }] : [])
                                 ].map((tab) => (
                                     <button
                                         key={tab.id}
@@ -304,6 +311,14 @@ export function ResultsView({ onRescan }: ResultsViewProps) {
                                     isMedia={isMedia}
                                 />
                             )}
+                            {viewMode === 'empty' && filteredResults && (
+                                <EmptyResultsView
+                                    emptyFiles={filteredResults.empty_files}
+                                    emptyDirs={filteredResults.empty_dirs}
+                                    selectedSet={selectedSet}
+                                    toggleSelection={toggleSelection}
+                                />
+                            )}
                             <div className="h-60" /> {/* Large buffer for footer room */}
                         </div>
                         <ScrollBar orientation="horizontal" />
@@ -1,4 +1,5 @@
 import { SidebarItem } from "./SidebarItem";
+import { ExclusionRulesPanel } from "./ExclusionRulesPanel";
 import { useDrives, Drive } from "../hooks/useDrives";
 import { useStore } from "../store/useStore";
 import {
@@ -14,7 +15,18 @@ import {
     HardDrive,
     FileText,
     ChevronRight,
-    Mic
+    Mic,
+    ScanSearch,
+    Link2,
+    Fingerprint,
+    ShieldCheck,
+    Layers,
+    ArrowDownWideNarrow,
+    Moon,
+    Gauge,
+    FileX,
+    Eraser,
+    X
 } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import { useQuery } from "@tanstack/react-query";
@@ -50,6 +62,7 @@ export function AppSidebar() {
     const { data: drives, isLoading: isLoadingDrives } = useDrives();
     const [isResetting, setIsResetting] = useState(false);
     const [isResetOpen, setIsResetOpen] = useState(false);
+    const [isPruning, setIsPruning] = useState(false);
     const [isSettingsOpen, setIsSettingsOpen] = useState(false);
     const {
         isScanning,
@@ -63,9 +76,46 @@ export function AppSidebar() {
         setScanZips,
         minFileSize,
         setMinFileSize,
+        contentDetection,
+        setContentDetection,
+        followSymlinks,
+        setFollowSymlinks,
+        hashAlgorithm,
+        setHashAlgorithm,
+        verifyContents,
+        setVerifyContents,
+        progressiveHashing,
+        setProgressiveHashing,
+        readOrder,
+        setReadOrder,
+        backgroundIo,
+        setBackgroundIo,
+        maxReadMbps,
+        setMaxReadMbps,
+        findEmpty,
+        setFindEmpty,
+        cacheLimit,
+        setCacheLimit,
         setActiveView
     } = useStore();
 
+    // Removes stale fingerprints but keeps the rest, unlike the full purge
+    const pruneCache = async (policy: { remove_missing?: boolean; prefixes?: string[]; vacuum?: boolean }, scope: string) => {
+        setIsPruning(true);
+        try {
+            const report = await invoke<{ missing: number; under_prefixes: number; evicted: number; remaining: number }>("prune_cache", { policy });
+            const removed = report.missing + report.under_prefixes + report.evicted;
+            toast.success(`Pruned ${removed} fingerprint(s)`, {
+                description: `${scope}. ${report.remaining} remain.`,
+            });
+        } catch (error) {
+            console.error("Failed to prune cache", error);
+            toast.error("Prune Failed", { description: String(error) });
+        } finally {
+            setIsPruning(false);
+        }
+    };
+
     const { data: systemNodes, isLoading: isLoadingNodes } = useQuery({
         queryKey: ["systemNodes"],
         queryFn: () => invoke<Drive[]>("get_system_nodes")
@@ -171,7 +221,7 @@ export function AppSidebar() {
                     {/* Collapsible Content */}
                     <div className={cn(
                         "space-y-2 overflow-hidden transition-all duration-300 ease-in-out",
-                        isSettingsOpen ? "max-h-[500px] opacity-100" : "max-h-0 opacity-0"
+                        isSettingsOpen ? "max-h-[1400px] opacity-100" : "max-h-0 opacity-0"
                     )}>
                         <div className="px-3 py-2 space-y-3 bg-muted/20 rounded-xl mb-4 border border-white/5">
                             <div className="flex items-center justify-between">
@@ -240,6 +290,183 @@ export function AppSidebar() {
                                 className="scale-75"
                             />
                         </div>
+
+                        <div className="flex items-center justify-between px-2 py-1 hover:bg-muted/30 rounded-lg transition-colors group" title="Walk into symlinked folders. Each folder is still scanned once.">
+                            <div className="flex items-center gap-2">
+                                <Link2 className={cn("w-3.5 h-3.5 shrink-0", followSymlinks ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60 group-hover:opacity-100 transition-opacity">Follow Symlinks</span>
+                            </div>
+                            <Switch
+                                checked={followSymlinks}
+                                onCheckedChange={setFollowSymlinks}
+                                disabled={isScanning}
+                                className="scale-75"
+                            />
+                        </div>
+
+                        <div className="px-2 py-1 space-y-1.5">
+                            <div className="flex items-center gap-2">
+                                <ScanSearch className={cn("w-3.5 h-3.5 shrink-0", contentDetection !== 'extension' ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60">Type Detection</span>
+                            </div>
+                            <div className="grid grid-cols-3 gap-1 bg-muted/20 rounded-lg p-0.5">
+                                {([['extension', 'Extension'], ['sniff', 'Content'], ['all', 'All Files']] as const).map(([mode, label]) => (
+                                    <button
+                                        key={mode}
+                                        onClick={() => setContentDetection(mode)}
+                                        disabled={isScanning}
+                                        className={cn(
+                                            "h-6 rounded-md text-[9px] font-black uppercase tracking-wider transition-colors",
+                                            contentDetection === mode ? "bg-background text-foreground shadow-sm" : "text-muted-foreground hover:text-foreground"
+                                        )}
+                                    >
+                                        {label}
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+
+                        <div className="px-2 py-1 space-y-1.5">
+                            <div className="flex items-center gap-2">
+                                <Fingerprint className={cn("w-3.5 h-3.5 shrink-0", hashAlgorithm !== 'blake3' ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60">Hash</span>
+                            </div>
+                            <div className="grid grid-cols-3 gap-1 bg-muted/20 rounded-lg p-0.5">
+                                {([['blake3', 'BLAKE3'], ['xxh3', 'XXH3'], ['sha256', 'SHA-256']] as const).map(([algorithm, label]) => (
+                                    <button
+                                        key={algorithm}
+                                        onClick={() => setHashAlgorithm(algorithm)}
+                                        disabled={isScanning}
+                                        className={cn(
+                                            "h-6 rounded-md text-[9px] font-black uppercase tracking-wider transition-colors",
+                                            hashAlgorithm === algorithm ? "bg-background text-foreground shadow-sm" : "text-muted-foreground hover:text-foreground"
+                                        )}
+                                    >
+                                        {label}
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+
+                        <div className="flex items-center justify-between px-2 py-1 hover:bg-muted/30 rounded-lg transition-colors group" title="Compare duplicates byte by byte before listing them. Slower, but never relies on hashes alone.">
+                            <div className="flex items-center gap-2">
+                                <ShieldCheck className={cn("w-3.5 h-3.5 shrink-0", verifyContents ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60 group-hover:opacity-100 transition-opacity">Byte Verify</span>
+                            </div>
+                            <Switch
+                                checked={verifyContents}
+                                onCheckedChange={setVerifyContents}
+                                disabled={isScanning}
+                                className="scale-75"
+                            />
+                        </div>
+
+                        <div className="flex items-center justify-between px-2 py-1 hover:bg-muted/30 rounded-lg transition-colors group" title="Hash large files in growing chunks and stop reading a file as soon as it stops matching the others.">
+                            <div className="flex items-center gap-2">
+                                <Layers className={cn("w-3.5 h-3.5 shrink-0", progressiveHashing ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60 group-hover:opacity-100 transition-opacity">Progressive Hash</span>
+                            </div>
+                            <Switch
+                                checked={progressiveHashing}
+                                onCheckedChange={setProgressiveHashing}
+                                disabled={isScanning}
+                                className="scale-75"
+                            />
+                        </div>
+
+                        <div className="px-2 py-1 space-y-1.5" title="Order of reads on each disk. Disk order follows the physical layout and helps spinning disks most.">
+                            <div className="flex items-center gap-2">
+                                <ArrowDownWideNarrow className={cn("w-3.5 h-3.5 shrink-0", readOrder !== 'discovery' ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60">Read Order</span>
+                            </div>
+                            <div className="grid grid-cols-3 gap-1 bg-muted/20 rounded-lg p-0.5">
+                                {([['discovery', 'Found'], ['inode', 'Inode'], ['physical', 'Disk']] as const).map(([order, label]) => (
+                                    <button
+                                        key={order}
+                                        onClick={() => setReadOrder(order)}
+                                        disabled={isScanning}
+                                        className={cn(
+                                            "h-6 rounded-md text-[9px] font-black uppercase tracking-wider transition-colors",
+                                            readOrder === order ? "bg-background text-foreground shadow-sm" : "text-muted-foreground hover:text-foreground"
+                                        )}
+                                    >
+                                        {label}
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+
+                        <div className="flex items-center justify-between px-2 py-1 hover:bg-muted/30 rounded-lg transition-colors group" title="Also list zero-byte files and folders that hold nothing but empty folders, so they can be cleaned up.">
+                            <div className="flex items-center gap-2">
+                                <FileX className={cn("w-3.5 h-3.5 shrink-0", findEmpty ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60 group-hover:opacity-100 transition-opacity">Empty Items</span>
+                            </div>
+                            <Switch
+                                checked={findEmpty}
+                                onCheckedChange={setFindEmpty}
+                                disabled={isScanning}
+                                className="scale-75"
+                            />
+                        </div>
+
+                        <div className="flex items-center justify-between px-2 py-1 hover:bg-muted/30 rounded-lg transition-colors group" title="Hash at low CPU and disk priority, without updating access times or filling the page cache. Keeps the machine responsive during long scans.">
+                            <div className="flex items-center gap-2">
+                                <Moon className={cn("w-3.5 h-3.5 shrink-0", backgroundIo ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60 group-hover:opacity-100 transition-opacity">Background Mode</span>
+                            </div>
+                            <Switch
+                                checked={backgroundIo}
+                                onCheckedChange={setBackgroundIo}
+                                disabled={isScanning}
+                                className="scale-75"
+                            />
+                        </div>
+
+                        <div className="px-2 py-1 space-y-1.5" title="Limit how fast files are read while hashing.">
+                            <div className="flex items-center gap-2">
+                                <Gauge className={cn("w-3.5 h-3.5 shrink-0", maxReadMbps !== null ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60">Read Cap</span>
+                            </div>
+                            <div className="grid grid-cols-3 gap-1 bg-muted/20 rounded-lg p-0.5">
+                                {([[null, 'Off'], [200, '200 MB/s'], [50, '50 MB/s']] as const).map(([mbps, label]) => (
+                                    <button
+                                        key={label}
+                                        onClick={() => setMaxReadMbps(mbps)}
+                                        disabled={isScanning}
+                                        className={cn(
+                                            "h-6 rounded-md text-[9px] font-black uppercase tracking-wider transition-colors",
+                                            maxReadMbps === mbps ? "bg-background text-foreground shadow-sm" : "text-muted-foreground hover:text-foreground"
+                                        )}
+                                    >
+                                        {label}
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+
+                        <div className="px-2 py-1 space-y-1.5" title="After each scan, forget the least recently seen fingerprints above this many.">
+                            <div className="flex items-center gap-2">
+                                <Database className={cn("w-3.5 h-3.5 shrink-0", cacheLimit !== null ? "text-primary" : "text-muted-foreground opacity-40")} />
+                                <span className="text-[10px] font-black uppercase tracking-widest opacity-60">Cache Limit</span>
+                            </div>
+                            <div className="grid grid-cols-3 gap-1 bg-muted/20 rounded-lg p-0.5">
+                                {([[null, 'Off'], [1000000, '1M'], [250000, '250K']] as const).map(([limit, label]) => (
+                                    <button
+                                        key={label}
+                                        onClick={() => setCacheLimit(limit)}
+                                        disabled={isScanning}
+                                        className={cn(
+                                            "h-6 rounded-md text-[9px] font-black uppercase tracking-wider transition-colors",
+                                            cacheLimit === limit ? "bg-background text-foreground shadow-sm" : "text-muted-foreground hover:text-foreground"
+                                        )}
+                                    >
+                                        {label}
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+
+                        <ExclusionRulesPanel disabled={isScanning} />
                     </div>
 
 
@@ -269,6 +496,14 @@ export function AppSidebar() {
                                             <div className="flex items-center gap-2 mb-0.5">
                                                 <HardDrive className="w-3 h-3 text-primary/70" />
                                                 <span className="text-xs font-bold text-foreground/90 truncate">{drive.name}</span>
+                                                <button
+                                                    onClick={() => pruneCache({ prefixes: [drive.mount_point] }, `Forgot everything under ${drive.mount_point}`)}
+                                                    disabled={isResetting || isPruning}
+                                                    className="ml-auto p-0.5 rounded text-muted-foreground/40 hover:text-destructive hover:bg-destructive/10 transition-colors"
+                                                    title="Forget fingerprints for this location only"
+                                                >
+                                                    <X className="w-3 h-3" />
+                                                </button>
                                             </div>
                                             <div className="text-[9px] font-mono text-muted-foreground truncate pl-5">
                                                 {drive.mount_point}
@@ -280,6 +515,14 @@ export function AppSidebar() {
                                             <div className="flex items-center gap-2 mb-0.5">
                                                 <FileText className="w-3 h-3 text-blue-400/70" />
                                                 <span className="text-xs font-bold text-foreground/90 truncate">{node.name}</span>
+                                                <button
+                                                    onClick={() => pruneCache({ prefixes: [node.mount_point] }, `Forgot everything under ${node.mount_point}`)}
+                                                    disabled={isResetting || isPruning}
+                                                    className="ml-auto p-0.5 rounded text-muted-foreground/40 hover:text-destructive hover:bg-destructive/10 transition-colors"
+                                                    title="Forget fingerprints for this location only"
+                                                >
+                                                    <X className="w-3 h-3" />
+                                                </button>
                                             </div>
                                             <div className="text-[9px] font-mono text-muted-foreground truncate pl-5">
                                                 {node.mount_point}
@@ -348,7 +591,16 @@ export function AppSidebar() {
                                     >
                                         {isResetting ? <Loader2 className="w-3 h-3 animate-spin mr-2" /> : "EXECUTE PURGE"}
                                     </AlertDialogAction>
-                                    <AlertDialogCancel disabled={isResetting} className="w-full h-8 text-xs font-bold uppercase tracking-wider border-white/5 hover:bg-white/5 hover:text-white bg-transparent">
+                                    <Button
+                                        variant="outline"
+                                        disabled={isResetting || isPruning}
+                                        onClick={() => pruneCache({ remove_missing: true, vacuum: true }, "Removed deleted files and compacted the database")}
+                                        className="w-full h-8 text-xs font-bold uppercase tracking-wider border-white/10 hover:bg-white/5 bg-transparent gap-2"
+                                    >
+                                        {isPruning ? <Loader2 className="w-3 h-3 animate-spin" /> : <Eraser className="w-3 h-3" />}
+                                        PRUNE DELETED FILES ONLY
+                                    </Button>
+                                    <AlertDialogCancel disabled={isResetting || isPruning} className="w-full h-8 text-xs font-bold uppercase tracking-wider border-white/5 hover:bg-white/5 hover:text-white bg-transparent">
                                         ABORT
                                     </AlertDialogCancel>
                                 </AlertDialogFooter>
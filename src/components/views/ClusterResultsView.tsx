@@ -1,5 +1,5 @@
 import React from "react";
-import { FileMetadata } from "../../store/useStore";
+import { DuplicateGroup, FileMetadata } from "../../store/useStore";
 import { formatSize, cn } from "../../lib/utils";
 import {
     CheckCircle2,
@@ -10,7 +10,7 @@ import {
 import { invoke } from "@tauri-apps/api/core";
 
 interface ClusterResultsViewProps {
-    scanResults: { groups: FileMetadata[][] };
+    scanResults: { groups: DuplicateGroup[] };
     selectedSet: Set<string>;
     toggleSelection: (path: string) => void;
     handlePreview: (e: React.MouseEvent, file: FileMetadata) => void;
@@ -28,7 +28,7 @@ export const ClusterResultsView: React.FC<ClusterResultsViewProps> = React.memo(
 
     // Flatten all files across all groups for linear keyboard navigation
     const allFiles = React.useMemo(() => {
-        return scanResults.groups.flatMap(group => group);
+        return scanResults.groups.flatMap(group => group.files);
     }, [scanResults]);
 
     React.useEffect(() => {
@@ -89,12 +89,20 @@ export const ClusterResultsView: React.FC<ClusterResultsViewProps> = React.memo(
                                             <FolderClosed className="w-2.5 h-2.5" />
                                         </div>
                                         <span className="text-[9px] font-black uppercase tracking-[0.2em] text-white/40 italic">
-                                            Cluster {idx + 1} &middot; {formatSize(group[0].size)}
+                                            Cluster {idx + 1} &middot; {formatSize(group.files[0].size)} &middot; {formatSize(group.reclaimable_bytes)} reclaimable
                                         </span>
+                                        {group.verification === 'byte_for_byte' && (
+                                            <span
+                                                className="text-[8px] font-black uppercase tracking-wider text-emerald-400/70"
+                                                title="Contents compared byte by byte"
+                                            >
+                                                Byte-verified
+                                            </span>
+                                        )}
                                     </div>
                                 </td>
                             </tr>
-                            {group.map((file) => {
+                            {group.files.map((file) => {
                                 const currentIndex = globalFileIndex++;
                                 const isFocused = focusedIndex === currentIndex;
                                 const isChecked = selectedSet.has(file.path);
@@ -143,6 +151,14 @@ export const ClusterResultsView: React.FC<ClusterResultsViewProps> = React.memo(
                                                 )}>
                                                     {folderPath}
                                                 </span>
+                                                {file.hard_links.length > 0 && (
+                                                    <span
+                                                        className="text-[8px] font-bold uppercase tracking-wider text-amber-400/60"
+                                                        title={file.hard_links.join('\n')}
+                                                    >
+                                                        +{file.hard_links.length} hard link{file.hard_links.length > 1 ? 's' : ''}
+                                                    </span>
+                                                )}
                                             </div>
                                         </td>
                                         <td className="px-5 py-2.5 text-right align-middle">
@@ -0,0 +1,130 @@
+import React from "react";
+import { cn } from "../../lib/utils";
+import {
+    CheckCircle2,
+    FileX,
+    FolderOpen,
+    ExternalLink,
+} from "lucide-react";
+import { invoke } from "@tauri-apps/api/core";
+
+interface EmptyResultsViewProps {
+    emptyFiles: string[];
+    emptyDirs: string[];
+    selectedSet: Set<string>;
+    toggleSelection: (path: string) => void;
+}
+
+export const EmptyResultsView: React.FC<EmptyResultsViewProps> = React.memo(({
+    emptyFiles,
+    emptyDirs,
+    selectedSet,
+    toggleSelection,
+}) => {
+    const handleReveal = async (e: React.MouseEvent, path: string) => {
+        e.stopPropagation();
+        await invoke("reveal_in_finder", { path });
+    };
+
+    const sections = [
+        { title: 'Empty Folders', icon: FolderOpen, paths: emptyDirs },
+        { title: 'Zero-Byte Files', icon: FileX, paths: emptyFiles },
+    ].filter(section => section.paths.length > 0);
+
+    const toggleAll = (paths: string[]) => {
+        const allSelected = paths.every(path => selectedSet.has(path));
+        paths
+            .filter(path => allSelected || !selectedSet.has(path))
+            .forEach(path => toggleSelection(path));
+    };
+
+    return (
+        <div className="overflow-hidden border border-white/5 rounded-2xl bg-[#0c0c0c] shadow-2xl">
+            <table className="min-w-full divide-y divide-white/5">
+                <thead className="bg-zinc-900 sticky top-0 z-10 backdrop-blur-md border-b border-white/5">
+                    <tr className="divide-x divide-white/5">
+                        <th className="w-10 px-5 py-3 text-left">
+                            <div className="w-3.5 h-3.5 border border-white/20 rounded-sm" />
+                        </th>
+                        <th className="px-5 py-3 text-left text-[8px] font-black text-white/40 uppercase tracking-widest">Name & Path</th>
+                        <th className="w-16 px-5 py-3 text-center text-[8px] font-black text-white/40 uppercase tracking-widest">Action</th>
+                    </tr>
+                </thead>
+                <tbody className="divide-y divide-white/[0.02]">
+                    {sections.map(section => (
+                        <React.Fragment key={section.title}>
+                            <tr className="bg-slate-800/50">
+                                <td colSpan={3} className="px-5 py-2">
+                                    <div className="flex items-center gap-2">
+                                        <div className="w-5 h-5 bg-white/10 rounded-md flex items-center justify-center text-white/60 border border-white/10">
+                                            <section.icon className="w-2.5 h-2.5" />
+                                        </div>
+                                        <span className="text-[9px] font-black uppercase tracking-[0.2em] text-white/40 italic">
+                                            {section.title} &middot; {section.paths.length}
+                                        </span>
+                                        <button
+                                            onClick={() => toggleAll(section.paths)}
+                                            className="ml-auto text-[8px] font-black uppercase tracking-wider text-emerald-400/70 hover:text-emerald-400 cursor-pointer"
+                                        >
+                                            {section.paths.every(path => selectedSet.has(path)) ? 'Deselect all' : 'Select all'}
+                                        </button>
+                                    </div>
+                                </td>
+                            </tr>
+                            {section.paths.map(path => {
+                                const isChecked = selectedSet.has(path);
+                                const name = path.split('/').pop() || path;
+                                const folderPath = path.split('/').slice(0, -1).join('/') || "/";
+
+                                return (
+                                    <tr
+                                        key={path}
+                                        onClick={() => toggleSelection(path)}
+                                        className={cn(
+                                            "group transition-all cursor-pointer",
+                                            isChecked ? "bg-emerald-500/10 hover:bg-emerald-500/20" : "hover:bg-white/[0.02]"
+                                        )}
+                                    >
+                                        <td className="px-5 py-2.5 align-middle">
+                                            <div className={cn(
+                                                "w-3.5 h-3.5 rounded-sm border flex items-center justify-center transition-all",
+                                                isChecked
+                                                    ? "bg-emerald-500 border-emerald-600 text-white shadow-sm"
+                                                    : "border-slate-200 group-hover:border-emerald-400/40"
+                                            )}>
+                                                {isChecked && <CheckCircle2 className="w-2.5 h-2.5" />}
+                                            </div>
+                                        </td>
+                                        <td className="px-5 py-2.5">
+                                            <div className="flex flex-col min-w-0">
+                                                <span className={cn(
+                                                    "text-[11px] font-bold tracking-tight truncate max-w-[350px]",
+                                                    isChecked ? "text-emerald-400" : "text-white"
+                                                )}>{name}</span>
+                                                <span className={cn(
+                                                    "text-[8px] font-medium truncate max-w-[450px]",
+                                                    isChecked ? "text-emerald-500/40" : "text-white/40"
+                                                )}>
+                                                    {folderPath}
+                                                </span>
+                                            </div>
+                                        </td>
+                                        <td className="px-5 py-2.5 text-center align-middle">
+                                            <button
+                                                onClick={(e) => handleReveal(e, path)}
+                                                className="p-1.5 rounded-lg text-white/20 hover:text-white hover:bg-white/10 transition-colors cursor-pointer"
+                                                title="Reveal in Finder"
+                                            >
+                                                <ExternalLink className="w-3 h-3" />
+                                            </button>
+                                        </td>
+                                    </tr>
+                                );
+                            })}
+                        </React.Fragment>
+                    ))}
+                </tbody>
+            </table>
+        </div>
+    );
+});
@@ -1,4 +1,4 @@
-import { FileMetadata } from "../store/useStore";
+import { DuplicateGroup, FileCategory, FileMetadata } from "../store/useStore";
 
 export type CategoryType = 'Images' | 'Videos' | 'Documents' | 'Archives' | 'Others';
 
@@ -18,7 +18,17 @@ export interface FolderData {
     }[];
 }
 
-export const transformToCategories = (groups: FileMetadata[][]): CategoryData[] => {
+// The backend reports each file's category (from its extension or sniffed bytes)
+export const categoryNames: Record<FileCategory, CategoryType> = {
+    image: 'Images',
+    video: 'Videos',
+    document: 'Documents',
+    archive: 'Archives',
+    audio: 'Others',
+    other: 'Others',
+};
+
+export const transformToCategories = (groups: DuplicateGroup[]): CategoryData[] => {
     const categories: Record<CategoryType, { files: FileMetadata[], totalSize: number, count: number }> = {
         Images: { files: [], totalSize: 0, count: 0 },
         Videos: { files: [], totalSize: 0, count: 0 },
@@ -27,20 +37,9 @@ export const transformToCategories = (groups: FileMetadata[][]): CategoryData[]
         Others: { files: [], totalSize: 0, count: 0 },
     };
 
-    const imageExts = ['jpg', 'jpeg', 'png', 'gif', 'webp', 'bmp', 'svg', 'tiff'];
-    const videoExts = ['mp4', 'mov', 'avi', 'mkv', 'webm', 'flv', 'wmv'];
-    const docExts = ['pdf', 'doc', 'docx', 'txt', 'md', 'rtf', 'xls', 'xlsx', 'ppt', 'pptx'];
-    const archiveExts = ['zip', 'rar', '7z', 'tar', 'gz', 'iso', 'dmg'];
-
     groups.forEach(cluster => {
-        cluster.forEach(file => {
-            const ext = file.path.split('.').pop()?.toLowerCase() || "";
-            let category: CategoryType = 'Others';
-
-            if (imageExts.includes(ext)) category = 'Images';
-            else if (videoExts.includes(ext)) category = 'Videos';
-            else if (docExts.includes(ext)) category = 'Documents';
-            else if (archiveExts.includes(ext)) category = 'Archives';
+        cluster.files.forEach(file => {
+            const category = categoryNames[file.category] ?? 'Others';
 
             categories[category].files.push(file);
             categories[category].totalSize += file.size;
@@ -58,12 +57,12 @@ export const transformToCategories = (groups: FileMetadata[][]): CategoryData[]
         .sort((a, b) => b.totalSize - a.totalSize);
 };
 
-export const transformToFolders = (groups: FileMetadata[][]): FolderData[] => {
+export const transformToFolders = (groups: DuplicateGroup[]): FolderData[] => {
     const folderMap = new Map<string, FolderData>();
 
     groups.forEach((group, groupIndex) => {
         const clusterId = `cluster-${groupIndex}`;
-        group.forEach(file => {
+        group.files.forEach(file => {
             const parentDir = file.path.split('/').slice(0, -1).join('/') || "/";
             if (!folderMap.has(parentDir)) {
                 folderMap.set(parentDir, {
@@ -1,15 +1,85 @@
 import { create } from 'zustand';
 
+export type FileCategory = 'image' | 'video' | 'audio' | 'document' | 'archive' | 'other';
+
+export type ContentDetection = 'extension' | 'sniff' | 'all';
+
+export type HashAlgorithm = 'blake3' | 'xxh3' | 'sha256';
+
+export type ReadOrder = 'discovery' | 'inode' | 'physical';
+
 export interface FileMetadata {
   path: string;
   size: number;
   modified: number;
   partial_hash: string | null;
   full_hash: string | null;
+  mime_type: string | null;
+  category: FileCategory;
+  dev: number;
+  inode: number;
+  nlink: number;
+  hard_links: string[];
+}
+
+export type Verification = 'full_hash' | 'byte_for_byte';
+
+export interface DuplicateGroup {
+  files: FileMetadata[];
+  reclaimable_bytes: number;
+  verification: Verification;
+}
+
+export interface MergedRoot {
+  path: string;
+  merged_into: string;
+}
+
+export interface SymlinkAlias {
+  path: string;
+  target: string;
+}
+
+export type ScanErrorPhase = 'traversal' | 'metadata' | 'partial_hash' | 'full_hash' | 'verification';
+
+export interface ScanError {
+  path: string;
+  phase: ScanErrorPhase;
+  kind: 'permission_denied' | 'not_found' | 'io';
+  os_code: number | null;
+  message: string;
+}
+
+export interface UnstableFile {
+  path: string;
+  size: number;
+  modified: number;
+  current_size: number | null;
+  current_modified: number | null;
 }
 
 export interface ScanResult {
-  groups: FileMetadata[][];
+  groups: DuplicateGroup[];
+  hash_algorithm: HashAlgorithm;
+  cancelled: boolean;
+  merged_roots: MergedRoot[];
+  symlink_aliases: SymlinkAlias[];
+  errors: ScanError[];
+  unstable_files: UnstableFile[];
+  empty_files: string[];
+  empty_dirs: string[];
+}
+
+export type ScanJobStatus = 'running' | 'completed' | 'cancelled' | { failed: { message: string } };
+
+export interface ScanJobSummary {
+  id: string;
+  paths: string[];
+  status: ScanJobStatus;
+  started_at: number;
+  finished_at: number | null;
+  progress: { current: number; total: number; file: string; } | null;
+  group_count: number | null;
 }
 
 interface UIState {
@@ -22,9 +92,20 @@ interface UIState {
   scanVideos: boolean;
   scanZips: boolean;
   minFileSize: number; // in bytes
+  contentDetection: ContentDetection;
+  followSymlinks: boolean;
+  hashAlgorithm: HashAlgorithm;
+  verifyContents: boolean;
+  progressiveHashing: boolean;
+  readOrder: ReadOrder;
+  backgroundIo: boolean;
+  maxReadMbps: number | null;
+  findEmpty: boolean;
+  cacheLimit: number | null;
   scanPhase: 'idle' | 'metadata' | 'partial' | 'full';
   scanTimestamp: number;
   scanProgress: { current: number; total: number; file: string; } | null;
+  activeJobId: string | null;
   isOnboarded: boolean;
   setScanning: (isScanning: boolean) => void;
   setScanPhase: (phase: 'idle' | 'metadata' | 'partial' | 'full') => void;
@@ -33,8 +114,19 @@ interface UIState {
   setScanVideos: (scanVideos: boolean) => void;
   setScanZips: (scanZips: boolean) => void;
   setMinFileSize: (size: number) => void;
+  setContentDetection: (mode: ContentDetection) => void;
+  setFollowSymlinks: (followSymlinks: boolean) => void;
+  setHashAlgorithm: (algorithm: HashAlgorithm) => void;
+  setVerifyContents: (verifyContents: boolean) => void;
+  setProgressiveHashing: (progressiveHashing: boolean) => void;
+  setReadOrder: (readOrder: ReadOrder) => void;
+  setBackgroundIo: (backgroundIo: boolean) => void;
+  setMaxReadMbps: (maxReadMbps: number | null) => void;
+  setFindEmpty: (findEmpty: boolean) => void;
+  setCacheLimit: (cacheLimit: number | null) => void;
   setScanTimestamp: (ts: number) => void;
   setScanProgress: (progress: { current: number; total: number; file: string; } | null) => void;
+  setActiveJobId: (jobId: string | null) => void;
   setOnboarded: (val: boolean) => void;
   addToQueue: (path: string) => void;
   removeFromQueue: (path: string) => void;
@@ -60,9 +152,20 @@ export const useStore = create<UIState>((set) => ({
   scanVideos: true,
   scanZips: true,
   minFileSize: 51200, // 50KB Default
+  contentDetection: 'extension',
+  followSymlinks: false,
+  hashAlgorithm: 'blake3',
+  verifyContents: false,
+  progressiveHashing: false,
+  readOrder: 'discovery',
+  backgroundIo: false,
+  maxReadMbps: null,
+  findEmpty: false,
+  cacheLimit: null,
   scanPhase: 'idle',
   scanTimestamp: 0,
   scanProgress: null,
+  activeJobId: null,
   isOnboarded: localStorage.getItem('dedupe-algo-onboarded') === 'true',
   activeView: 'jarvis',
   explorerPath: null,
@@ -73,8 +176,19 @@ export const useStore = create<UIState>((set) => ({
   setScanVideos: (scanVideos) => set({ scanVideos }),
   setScanZips: (scanZips) => set({ scanZips }),
   setMinFileSize: (minFileSize) => set({ minFileSize }),
+  setContentDetection: (contentDetection) => set({ contentDetection }),
+  setFollowSymlinks: (followSymlinks) => set({ followSymlinks }),
+  setHashAlgorithm: (hashAlgorithm) => set({ hashAlgorithm }),
+  setVerifyContents: (verifyContents) => set({ verifyContents }),
+  setProgressiveHashing: (progressiveHashing) => set({ progressiveHashing }),
+  setReadOrder: (readOrder) => set({ readOrder }),
+  setBackgroundIo: (backgroundIo) => set({ backgroundIo }),
+  setMaxReadMbps: (maxReadMbps) => set({ maxReadMbps }),
+  setFindEmpty: (findEmpty) => set({ findEmpty }),
+  setCacheLimit: (cacheLimit) => set({ cacheLimit }),
   setScanTimestamp: (ts) => set({ scanTimestamp: ts }),
   setScanProgress: (scanProgress) => set({ scanProgress }),
+  setActiveJobId: (activeJobId) => set({ activeJobId }),
   setActiveView: (activeView) => set({ activeView }),
   setExplorerPath: (explorerPath) => set({ explorerPath }),
   setOnboarded: (val: boolean) => {
@@ -100,7 +214,7 @@ export const useStore = create<UIState>((set) => ({
     const newSelection: string[] = [];
     state.scanResults.groups.forEach((group) => {
       // Sort group by modified date
-      const sorted = [...group].sort((a, b) =>
+      const sorted = [...group.files].sort((a, b) =>
         criteria === "newest" ? b.modified - a.modified : a.modified - b.modified
       );
 
@@ -116,12 +230,23 @@ export const useStore = create<UIState>((set) => ({
   removeDeletedFromResults: (paths: string[]) => set((state) => {
     if (!state.scanResults) return state;
 
-    const newGroups = state.scanResults.groups.map(group =>
-      group.filter(file => !paths.includes(file.path))
-    ).filter(group => group.length > 1); // Only keep groups that still have duplicates
+    const newGroups = state.scanResults.groups.map(group => {
+      const files = group.files.filter(file => !paths.includes(file.path));
+      const size = files[0]?.size || 0;
+      return {
+        ...group,
+        files,
+        reclaimable_bytes: Math.min(group.reclaimable_bytes, size * Math.max(0, files.length - 1)),
+      };
+    }).filter(group => group.files.length > 1); // Only keep groups that still have duplicates
 
     return {
-      scanResults: { ...state.scanResults, groups: newGroups },
+      scanResults: {
+        ...state.scanResults,
+        groups: newGroups,
+        empty_files: state.scanResults.empty_files.filter(p => !paths.includes(p)),
+        empty_dirs: state.scanResults.empty_dirs.filter(p => !paths.includes(p)),
+      },
       selectionQueue: state.selectionQueue.filter(p => !paths.includes(p))
     };
   }),